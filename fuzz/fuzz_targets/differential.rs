@@ -0,0 +1,14 @@
+//! Runs arbitrary byte strings as differential programs against every queue.
+//!
+//! ```text
+//! cargo +nightly fuzz run differential
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lock_free_spsc::spsc::differential::Program;
+
+fuzz_target!(|data: &[u8]| {
+    Program::decode(data).run();
+});
@@ -1,4 +1,4 @@
-///! This module is from the `crossbeam-utils` crate.
+//! This module is from the `crossbeam-utils` crate.
 use core::fmt;
 use core::ops::{Deref, DerefMut};
 
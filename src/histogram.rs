@@ -0,0 +1,247 @@
+//! Log-bucketed latency histogram in the style of HdrHistogram.
+//!
+//! Values are grouped by their power of two, and every power of two is split
+//! into [`SUB_BUCKETS`] equal sub-buckets. A recorded value is therefore kept
+//! with a relative error below `1 / SUB_BUCKETS` (under 1%) across the whole
+//! `u64` range, in a fixed table of counters: recording is an index
+//! computation and an increment, with no allocation on the hot path.
+//!
+//! # Example
+//! ```
+//! use lock_free_spsc::histogram::Histogram;
+//!
+//! let mut latencies = Histogram::new();
+//! for nanos in 1..=1000 {
+//!     latencies.record(nanos);
+//! }
+//! assert_eq!(latencies.count(), 1000);
+//! assert_eq!(latencies.max(), 1000);
+//! let p99 = latencies.value_at_quantile(0.99);
+//! assert!((985..=995).contains(&p99));
+//! ```
+
+/// Number of sub-buckets per power of two.
+pub const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+const SUB_BUCKET_BITS: u32 = 7;
+/// Values below `SUB_BUCKETS` get one counter each; every octave above that
+/// up to `2^63..2^64` gets `SUB_BUCKETS` counters.
+const BUCKETS: usize = SUB_BUCKETS * (64 - SUB_BUCKET_BITS as usize + 1);
+
+/// A histogram of `u64` values, typically latencies in nanoseconds or ticks.
+#[derive(Clone)]
+pub struct Histogram {
+    counts: Box<[u64]>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        Histogram {
+            counts: vec![0; BUCKETS].into_boxed_slice(),
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Records one occurrence of `value`.
+    #[inline]
+    pub fn record(&mut self, value: u64) {
+        self.record_n(value, 1);
+    }
+
+    /// Records `n` occurrences of `value`.
+    #[inline]
+    pub fn record_n(&mut self, value: u64, n: u64) {
+        if n == 0 {
+            return;
+        }
+        self.counts[index_of(value)] += n;
+        self.count += n;
+        self.sum += value as u128 * n as u128;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Adds every value recorded in `other` to this histogram.
+    pub fn merge(&mut self, other: &Histogram) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Removes every recorded value.
+    pub fn reset(&mut self) {
+        self.counts.fill(0);
+        self.count = 0;
+        self.sum = 0;
+        self.min = u64::MAX;
+        self.max = 0;
+    }
+
+    /// Returns the number of recorded values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns `true` if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the smallest recorded value, or `0` if the histogram is empty.
+    pub fn min(&self) -> u64 {
+        if self.is_empty() { 0 } else { self.min }
+    }
+
+    /// Returns the largest recorded value, or `0` if the histogram is empty.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the exact mean of the recorded values, or `0.0` if empty.
+    pub fn mean(&self) -> f64 {
+        if self.is_empty() {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+
+    /// Returns the value below which a `quantile` fraction of the recorded
+    /// values fall, e.g. `0.99` for the 99th percentile.
+    ///
+    /// The result is the upper bound of the sub-bucket holding that value,
+    /// capped at [`max`](Self::max), so it never under-reports. Returns `0`
+    /// if the histogram is empty.
+    ///
+    /// # Panics
+    /// Panics if `quantile` is not within `0.0..=1.0`.
+    pub fn value_at_quantile(&self, quantile: f64) -> u64 {
+        assert!(
+            (0.0..=1.0).contains(&quantile),
+            "quantile must be within 0.0..=1.0"
+        );
+        if self.is_empty() {
+            return 0;
+        }
+        // The rank of the value we are after, counting from one.
+        let rank = ((quantile * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return highest_equivalent(index).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Histogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Histogram")
+            .field("count", &self.count)
+            .field("min", &self.min())
+            .field("max", &self.max)
+            .field("mean", &self.mean())
+            .finish()
+    }
+}
+
+/// Maps a value to its counter.
+#[inline(always)]
+fn index_of(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    // Keep the top `SUB_BUCKET_BITS + 1` bits: the leading one picks the
+    // octave, the bits after it the sub-bucket.
+    let octave = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+    let sub_bucket = (value >> octave) as usize - SUB_BUCKETS;
+    SUB_BUCKETS * (octave as usize + 1) + sub_bucket
+}
+
+/// Returns the largest value that maps to the counter at `index`.
+fn highest_equivalent(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let octave = (index / SUB_BUCKETS - 1) as u32;
+    let sub_bucket = (index % SUB_BUCKETS + SUB_BUCKETS) as u64;
+    let lowest = sub_bucket << octave;
+    lowest + ((1u64 << octave) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BUCKETS, Histogram, SUB_BUCKETS, highest_equivalent, index_of};
+
+    #[test]
+    fn buckets_cover_the_whole_range() {
+        assert_eq!(index_of(0), 0);
+        assert_eq!(index_of(SUB_BUCKETS as u64 - 1), SUB_BUCKETS - 1);
+        assert_eq!(index_of(SUB_BUCKETS as u64), SUB_BUCKETS);
+        assert_eq!(index_of(u64::MAX), BUCKETS - 1);
+        assert_eq!(highest_equivalent(BUCKETS - 1), u64::MAX);
+        for value in [1, 127, 128, 129, 255, 256, 1000, 123_456_789, 1 << 40] {
+            let index = index_of(value);
+            assert!(highest_equivalent(index) >= value);
+            // Within 1% of the recorded value.
+            assert!(highest_equivalent(index) - value <= value / SUB_BUCKETS as u64);
+            assert_eq!(index_of(highest_equivalent(index)), index);
+        }
+    }
+
+    #[test]
+    fn quantiles() {
+        let mut histogram = Histogram::new();
+        assert_eq!(histogram.value_at_quantile(0.5), 0);
+        for value in 1..=10_000 {
+            histogram.record(value);
+        }
+        histogram.record_n(1_000_000, 10);
+        assert_eq!(histogram.count(), 10_010);
+        assert_eq!((histogram.min(), histogram.max()), (1, 1_000_000));
+        let p50 = histogram.value_at_quantile(0.5);
+        assert!((5000..=5050).contains(&p50), "{p50}");
+        let p99 = histogram.value_at_quantile(0.99);
+        assert!((9900..=9990).contains(&p99), "{p99}");
+        assert_eq!(histogram.value_at_quantile(1.0), 1_000_000);
+        assert_eq!(histogram.value_at_quantile(0.0), 1);
+    }
+
+    #[test]
+    fn merge_and_reset() {
+        let mut a = Histogram::new();
+        let mut b = Histogram::new();
+        a.record(10);
+        b.record(20);
+        b.record(30);
+        a.merge(&b);
+        assert_eq!(a.count(), 3);
+        assert_eq!(a.mean(), 20.0);
+        assert_eq!((a.min(), a.max()), (10, 30));
+        a.reset();
+        assert!(a.is_empty());
+        assert_eq!((a.min(), a.max()), (0, 0));
+        a.record(5);
+        assert_eq!(a.value_at_quantile(0.5), 5);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod cache_padded;
+pub mod histogram;
 pub mod spsc;
+mod sync;
@@ -1,3 +1,835 @@
+//! `spsc-bench`: throughput and round-trip latency of the crate's channels,
+//! measured against `std::sync::mpsc`.
+//!
+//! ```text
+//! cargo run --release --bin spsc-bench -- --size 64 --capacity 1024 --producer-cpu 2 --consumer-cpu 3
+//! cargo run --release --bin spsc-bench -- --mode throughput --format json > bench.json
+//! cargo run --release --bin spsc-bench -- --mode ping-pong --clock tsc
+//! ```
+//!
+//! Run with `--help` for every option. Each configuration runs `--runs` times
+//! and the median is reported. Ping-pong runs instead time every message into
+//! a [`Histogram`], merged over all runs, and report its percentiles.
+
+use lock_free_spsc::histogram::Histogram;
+use lock_free_spsc::spsc::bounded_spsc::{self, BoundedSpscChannel};
+use lock_free_spsc::spsc::unbounded_spsc::{self, LimitExceeded, UnboundSpscChannel};
+use std::hint::{black_box, spin_loop};
+use std::io;
+use std::sync::{Arc, Barrier, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage: spsc-bench [OPTIONS]
+
+Options:
+  --mode <MODE>          throughput, latency, ping-pong or all [default: all]
+  --channel <CHANNEL>    bounded, unbounded, std-mpsc or all [default: all]
+  --messages <N>         messages per throughput run [default: 10000000]
+  --round-trips <N>      round trips per latency or ping-pong run [default: 1000000]
+  --size <BYTES>         message size: 8, 16, 32, 64, 128, 256, 512, 1024 or 4096 [default: 8]
+  --capacity <N>         capacity of the bounded channels [default: 1024]
+  --batch <N>            messages sent per burst and received per poll [default: 1]
+  --producer-cpu <CPU>   pin the producer (the pinging side in latency runs) to a CPU
+  --consumer-cpu <CPU>   pin the consumer (the echoing side in latency runs) to a CPU
+  --clock <CLOCK>        ping-pong timestamps: instant or tsc (x86_64 only) [default: instant]
+  --runs <N>             runs per configuration; the median is reported [default: 3]
+  --format <FORMAT>      table or json [default: table]
+  -h, --help             print this help
+
+std-mpsc uses `sync_channel` with the same capacity. Ping-pong reports one-way
+latencies of both legs and round-trip latencies; one-way numbers with `tsc`
+assume the CPUs' time stamp counters are synchronized.";
+
+const SIZES: [usize; 9] = [8, 16, 32, 64, 128, 256, 512, 1024, 4096];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Throughput,
+    Latency,
+    PingPong,
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Throughput, Mode::Latency, Mode::PingPong];
+
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Throughput => "throughput",
+            Mode::Latency => "latency",
+            Mode::PingPong => "ping-pong",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flavour {
+    Bounded,
+    Unbounded,
+    StdMpsc,
+}
+
+impl Flavour {
+    fn name(self) -> &'static str {
+        match self {
+            Flavour::Bounded => "bounded",
+            Flavour::Unbounded => "unbounded",
+            Flavour::StdMpsc => "std-mpsc",
+        }
+    }
+
+    fn is_bounded(self) -> bool {
+        self != Flavour::Unbounded
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClockKind {
+    Instant,
+    Tsc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+struct Options {
+    modes: Vec<Mode>,
+    flavours: Vec<Flavour>,
+    messages: u64,
+    round_trips: u64,
+    size: usize,
+    capacity: usize,
+    batch: usize,
+    producer_cpu: Option<usize>,
+    consumer_cpu: Option<usize>,
+    clock: ClockKind,
+    runs: usize,
+    format: Format,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            modes: Mode::ALL.to_vec(),
+            flavours: vec![Flavour::Bounded, Flavour::Unbounded, Flavour::StdMpsc],
+            messages: 10_000_000,
+            round_trips: 1_000_000,
+            size: 8,
+            capacity: 1024,
+            batch: 1,
+            producer_cpu: None,
+            consumer_cpu: None,
+            clock: ClockKind::Instant,
+            runs: 3,
+            format: Format::Table,
+        }
+    }
+}
+
+impl Options {
+    /// Parses `--name value` and `--name=value` arguments.
+    ///
+    /// Returns `Ok(None)` when help was requested.
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                return Ok(None);
+            }
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let value = match inline.or_else(|| args.next()) {
+                Some(value) => value,
+                None => return Err(format!("missing value for {name}")),
+            };
+            match name.as_str() {
+                "--mode" => {
+                    options.modes = match value.as_str() {
+                        "throughput" => vec![Mode::Throughput],
+                        "latency" => vec![Mode::Latency],
+                        "ping-pong" => vec![Mode::PingPong],
+                        "all" => Mode::ALL.to_vec(),
+                        _ => return Err(format!("unknown mode `{value}`")),
+                    }
+                }
+                "--channel" => {
+                    options.flavours = match value.as_str() {
+                        "bounded" => vec![Flavour::Bounded],
+                        "unbounded" => vec![Flavour::Unbounded],
+                        "std-mpsc" => vec![Flavour::StdMpsc],
+                        "all" => Options::default().flavours,
+                        _ => return Err(format!("unknown channel `{value}`")),
+                    }
+                }
+                "--messages" => options.messages = number(&name, &value)?,
+                "--round-trips" => options.round_trips = number(&name, &value)?,
+                "--size" => {
+                    options.size = number(&name, &value)?;
+                    if !SIZES.contains(&options.size) {
+                        return Err(format!("unsupported message size {value}"));
+                    }
+                }
+                "--capacity" => options.capacity = number(&name, &value)?,
+                "--batch" => options.batch = number(&name, &value)?,
+                "--producer-cpu" => options.producer_cpu = Some(number(&name, &value)?),
+                "--consumer-cpu" => options.consumer_cpu = Some(number(&name, &value)?),
+                "--clock" => {
+                    options.clock = match value.as_str() {
+                        "instant" => ClockKind::Instant,
+                        "tsc" if cfg!(target_arch = "x86_64") => ClockKind::Tsc,
+                        "tsc" => return Err("the tsc clock needs x86_64".to_string()),
+                        _ => return Err(format!("unknown clock `{value}`")),
+                    }
+                }
+                "--runs" => options.runs = number(&name, &value)?,
+                "--format" => {
+                    options.format = match value.as_str() {
+                        "table" => Format::Table,
+                        "json" => Format::Json,
+                        _ => return Err(format!("unknown format `{value}`")),
+                    }
+                }
+                _ => return Err(format!("unknown option `{name}`")),
+            }
+        }
+        if options.capacity == 0 || options.batch == 0 || options.runs == 0 {
+            return Err("--capacity, --batch and --runs must be at least 1".to_string());
+        }
+        Ok(Some(options))
+    }
+}
+
+fn number<N: std::str::FromStr>(name: &str, value: &str) -> Result<N, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for {name}"))
+}
+
+/// A message of `N` bytes whose first eight carry a sequence number.
+#[derive(Clone, Copy)]
+struct Message<const N: usize>([u8; N]);
+
+impl<const N: usize> Message<N> {
+    fn new(seq: u64) -> Self {
+        let mut bytes = [0; N];
+        bytes[..8].copy_from_slice(&seq.to_le_bytes());
+        Message(bytes)
+    }
+
+    fn seq(&self) -> u64 {
+        u64::from_le_bytes(self.0[..8].try_into().unwrap())
+    }
+}
+
+/// Timestamp source for ping-pong runs.
+#[derive(Clone, Copy)]
+enum Clock {
+    /// Nanoseconds since `Instant`.
+    Instant(Instant),
+    /// Raw time stamp counter ticks, calibrated against `Instant`.
+    #[cfg(target_arch = "x86_64")]
+    Tsc { nanos_per_tick: f64 },
+}
+
+impl Clock {
+    fn new(kind: ClockKind) -> Clock {
+        match kind {
+            ClockKind::Instant => Clock::Instant(Instant::now()),
+            #[cfg(target_arch = "x86_64")]
+            ClockKind::Tsc => {
+                let (started, ticks) = (Instant::now(), rdtsc());
+                thread::sleep(Duration::from_millis(50));
+                let elapsed = started.elapsed().as_nanos() as f64;
+                Clock::Tsc {
+                    nanos_per_tick: elapsed / (rdtsc() - ticks) as f64,
+                }
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            ClockKind::Tsc => unreachable!("rejected while parsing"),
+        }
+    }
+
+    #[inline(always)]
+    fn now(self) -> u64 {
+        match self {
+            Clock::Instant(epoch) => epoch.elapsed().as_nanos() as u64,
+            #[cfg(target_arch = "x86_64")]
+            Clock::Tsc { .. } => rdtsc(),
+        }
+    }
+
+    fn to_nanos(self, ticks: u64) -> f64 {
+        match self {
+            Clock::Instant(_) => ticks as f64,
+            #[cfg(target_arch = "x86_64")]
+            Clock::Tsc { nanos_per_tick } => ticks as f64 * nanos_per_tick,
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn rdtsc() -> u64 {
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+/// A channel under test, driven with spinning sends and receives.
+trait Channel {
+    type Tx<M: Send + 'static>: Send + 'static;
+    type Rx<M: Send + 'static>: Send + 'static;
+
+    fn split<M: Send + 'static>(capacity: usize) -> (Self::Tx<M>, Self::Rx<M>);
+    fn try_send<M: Send + 'static>(tx: &Self::Tx<M>, message: M) -> Result<(), M>;
+    fn try_recv<M: Send + 'static>(rx: &Self::Rx<M>) -> Option<M>;
+
+    /// Sends a message, spinning while the channel is full.
+    fn send<M: Send + 'static>(tx: &Self::Tx<M>, mut message: M) {
+        while let Err(rejected) = Self::try_send(tx, message) {
+            message = rejected;
+            spin_loop();
+        }
+    }
+
+    /// Receives a message, spinning while the channel is empty.
+    fn recv<M: Send + 'static>(rx: &Self::Rx<M>) -> M {
+        loop {
+            match Self::try_recv(rx) {
+                Some(message) => return message,
+                None => spin_loop(),
+            }
+        }
+    }
+}
+
+struct Bounded;
+
+impl Channel for Bounded {
+    type Tx<M: Send + 'static> = bounded_spsc::Sender<M>;
+    type Rx<M: Send + 'static> = bounded_spsc::Receiver<M>;
+
+    fn split<M: Send + 'static>(capacity: usize) -> (Self::Tx<M>, Self::Rx<M>) {
+        BoundedSpscChannel::split(capacity)
+    }
+    fn try_send<M: Send + 'static>(tx: &Self::Tx<M>, message: M) -> Result<(), M> {
+        tx.send(message)
+    }
+    fn try_recv<M: Send + 'static>(rx: &Self::Rx<M>) -> Option<M> {
+        rx.recv()
+    }
+}
+
+struct Unbounded;
+
+impl Channel for Unbounded {
+    type Tx<M: Send + 'static> = unbounded_spsc::Sender<M>;
+    type Rx<M: Send + 'static> = unbounded_spsc::Receiver<M>;
+
+    fn split<M: Send + 'static>(_: usize) -> (Self::Tx<M>, Self::Rx<M>) {
+        UnboundSpscChannel::split()
+    }
+    fn try_send<M: Send + 'static>(tx: &Self::Tx<M>, message: M) -> Result<(), M> {
+        tx.send(message).map_err(LimitExceeded::into_inner)
+    }
+    fn try_recv<M: Send + 'static>(rx: &Self::Rx<M>) -> Option<M> {
+        rx.recv()
+    }
+}
+
+struct StdMpsc;
+
+impl Channel for StdMpsc {
+    type Tx<M: Send + 'static> = mpsc::SyncSender<M>;
+    type Rx<M: Send + 'static> = mpsc::Receiver<M>;
+
+    fn split<M: Send + 'static>(capacity: usize) -> (Self::Tx<M>, Self::Rx<M>) {
+        mpsc::sync_channel(capacity)
+    }
+    fn try_send<M: Send + 'static>(tx: &Self::Tx<M>, message: M) -> Result<(), M> {
+        tx.try_send(message).map_err(|err| match err {
+            mpsc::TrySendError::Full(message) => message,
+            mpsc::TrySendError::Disconnected(_) => panic!("receiver disconnected"),
+        })
+    }
+    fn try_recv<M: Send + 'static>(rx: &Self::Rx<M>) -> Option<M> {
+        rx.try_recv().ok()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_cpu(cpu: usize) -> io::Result<()> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "CPU index out of range",
+        ));
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        if libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_to_cpu(_: usize) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU pinning needs Linux",
+    ))
+}
+
+/// Spawns a thread, pinned to `cpu` if one is given.
+fn spawn_pinned<R: Send + 'static>(
+    cpu: Option<usize>,
+    f: impl FnOnce() -> R + Send + 'static,
+) -> thread::JoinHandle<R> {
+    thread::spawn(move || {
+        if let Some(cpu) = cpu
+            && let Err(err) = pin_to_cpu(cpu)
+        {
+            eprintln!("spsc-bench: could not pin to CPU {cpu}: {err}");
+        }
+        f()
+    })
+}
+
+/// Streams `messages` messages from a producer to a consumer thread and
+/// returns the time the consumer took to receive all of them.
+fn throughput<C: Channel, const N: usize>(options: &Options) -> Duration {
+    let (tx, rx) = C::split::<Message<N>>(options.capacity);
+    let (messages, batch) = (options.messages, options.batch as u64);
+    let start = Arc::new(Barrier::new(2));
+
+    let producer = spawn_pinned(options.producer_cpu, {
+        let start = Arc::clone(&start);
+        move || {
+            start.wait();
+            let mut seq = 0;
+            while seq < messages {
+                let burst_end = (seq + batch).min(messages);
+                for seq in seq..burst_end {
+                    C::send(&tx, Message::new(seq));
+                }
+                seq = burst_end;
+            }
+        }
+    });
+    let consumer = spawn_pinned(options.consumer_cpu, move || {
+        start.wait();
+        let started = Instant::now();
+        let mut expected = 0;
+        while expected < messages {
+            let mut polled = 0;
+            while polled < batch
+                && let Some(message) = C::try_recv(&rx)
+            {
+                assert_eq!(message.seq(), expected, "message out of order");
+                black_box(&message);
+                expected += 1;
+                polled += 1;
+            }
+            if polled == 0 {
+                spin_loop();
+            }
+        }
+        started.elapsed()
+    });
+
+    producer.join().unwrap();
+    consumer.join().unwrap()
+}
+
+/// Bounces a message between two threads over a pair of channels and
+/// returns the total time for `round_trips` round trips.
+fn latency<C: Channel, const N: usize>(options: &Options) -> Duration {
+    let (ping_tx, ping_rx) = C::split::<Message<N>>(options.capacity);
+    let (pong_tx, pong_rx) = C::split::<Message<N>>(options.capacity);
+    let round_trips = options.round_trips;
+
+    let echo = spawn_pinned(options.consumer_cpu, move || {
+        for _ in 0..round_trips {
+            C::send(&pong_tx, C::recv(&ping_rx));
+        }
+    });
+    let pinger = spawn_pinned(options.producer_cpu, move || {
+        let started = Instant::now();
+        for seq in 0..round_trips {
+            C::send(&ping_tx, Message::new(seq));
+            assert_eq!(C::recv(&pong_rx).seq(), seq, "reply out of order");
+        }
+        started.elapsed()
+    });
+
+    echo.join().unwrap();
+    pinger.join().unwrap()
+}
+
+/// Per-message latencies of a ping-pong run, in clock ticks.
+struct Latencies {
+    /// Both legs: pinger to echo and echo back to pinger.
+    one_way: Histogram,
+    round_trip: Histogram,
+}
+
+/// Bounces a timestamped message between two threads over a pair of
+/// channels, recording each leg and each round trip.
+fn ping_pong<C: Channel, const N: usize>(options: &Options, clock: Clock) -> Latencies {
+    let (ping_tx, ping_rx) = C::split::<Message<N>>(options.capacity);
+    let (pong_tx, pong_rx) = C::split::<Message<N>>(options.capacity);
+    let round_trips = options.round_trips;
+
+    let echo = spawn_pinned(options.consumer_cpu, move || {
+        let mut forward = Histogram::new();
+        for _ in 0..round_trips {
+            let ping = C::recv(&ping_rx);
+            let received = clock.now();
+            // Unsynchronized counters could put the receive before the send.
+            forward.record(received.saturating_sub(ping.seq()));
+            C::send(&pong_tx, Message::new(clock.now()));
+        }
+        forward
+    });
+    let pinger = spawn_pinned(options.producer_cpu, move || {
+        let mut back = Histogram::new();
+        let mut round_trip = Histogram::new();
+        for _ in 0..round_trips {
+            let sent = clock.now();
+            C::send(&ping_tx, Message::new(sent));
+            let pong = C::recv(&pong_rx);
+            let received = clock.now();
+            back.record(received.saturating_sub(pong.seq()));
+            round_trip.record(received - sent);
+        }
+        (back, round_trip)
+    });
+
+    let mut one_way = echo.join().unwrap();
+    let (back, round_trip) = pinger.join().unwrap();
+    one_way.merge(&back);
+    Latencies {
+        one_way,
+        round_trip,
+    }
+}
+
+/// What one run of a mode produced.
+enum Outcome {
+    Elapsed(Duration),
+    Latencies(Latencies),
+}
+
+fn run_once<C: Channel>(mode: Mode, options: &Options, clock: Clock) -> Outcome {
+    macro_rules! sized {
+        ($($size:literal)*) => {
+            match (mode, options.size) {
+                $(
+                    (Mode::Throughput, $size) => Outcome::Elapsed(throughput::<C, $size>(options)),
+                    (Mode::Latency, $size) => Outcome::Elapsed(latency::<C, $size>(options)),
+                    (Mode::PingPong, $size) => {
+                        Outcome::Latencies(ping_pong::<C, $size>(options, clock))
+                    }
+                )*
+                (_, size) => unreachable!("unsupported message size {size}"),
+            }
+        };
+    }
+    sized!(8 16 32 64 128 256 512 1024 4096)
+}
+
+/// The median result of one throughput or latency configuration.
+struct Measurement {
+    flavour: Flavour,
+    mode: Mode,
+    count: u64,
+    elapsed: Duration,
+}
+
+impl Measurement {
+    fn per_second(&self) -> f64 {
+        self.count as f64 / self.elapsed.as_secs_f64()
+    }
+
+    fn nanos_per_op(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.count as f64
+    }
+}
+
+/// The merged latencies of every run of one ping-pong configuration.
+struct Distribution {
+    flavour: Flavour,
+    latencies: Latencies,
+    clock: Clock,
+}
+
+impl Distribution {
+    const QUANTILES: [(&str, f64); 3] = [("p50", 0.5), ("p99", 0.99), ("p99.9", 0.999)];
+
+    /// Returns the metric name with p50, p99, p99.9 and max in nanoseconds.
+    fn summaries(&self) -> [(&'static str, u64, [f64; 4]); 2] {
+        let summary = |histogram: &Histogram| {
+            let [p50, p99, p999] =
+                Self::QUANTILES.map(|(_, q)| self.clock.to_nanos(histogram.value_at_quantile(q)));
+            [p50, p99, p999, self.clock.to_nanos(histogram.max())]
+        };
+        let Latencies {
+            one_way,
+            round_trip,
+        } = &self.latencies;
+        [
+            ("one-way", one_way.count(), summary(one_way)),
+            ("round-trip", round_trip.count(), summary(round_trip)),
+        ]
+    }
+}
+
+/// The summary of one configuration.
+enum Report {
+    Elapsed(Measurement),
+    Latencies(Distribution),
+}
+
+fn measure(flavour: Flavour, mode: Mode, options: &Options, clock: Clock) -> Report {
+    let runs = (0..options.runs).map(|_| match flavour {
+        Flavour::Bounded => run_once::<Bounded>(mode, options, clock),
+        Flavour::Unbounded => run_once::<Unbounded>(mode, options, clock),
+        Flavour::StdMpsc => run_once::<StdMpsc>(mode, options, clock),
+    });
+
+    let mut elapsed = Vec::new();
+    let mut merged = Latencies {
+        one_way: Histogram::new(),
+        round_trip: Histogram::new(),
+    };
+    for outcome in runs {
+        match outcome {
+            Outcome::Elapsed(run) => elapsed.push(run),
+            Outcome::Latencies(run) => {
+                merged.one_way.merge(&run.one_way);
+                merged.round_trip.merge(&run.round_trip);
+            }
+        }
+    }
+    if mode == Mode::PingPong {
+        return Report::Latencies(Distribution {
+            flavour,
+            latencies: merged,
+            clock,
+        });
+    }
+    elapsed.sort();
+    let count = match mode {
+        Mode::Throughput => options.messages,
+        _ => options.round_trips,
+    };
+    Report::Elapsed(Measurement {
+        flavour,
+        mode,
+        count,
+        elapsed: elapsed[elapsed.len() / 2],
+    })
+}
+
+fn print_table(options: &Options, results: &[Measurement], distributions: &[Distribution]) {
+    if !results.is_empty() {
+        print_measurements(options, results);
+    }
+    if !distributions.is_empty() {
+        if !results.is_empty() {
+            println!();
+        }
+        print_distributions(options, distributions);
+    }
+}
+
+fn print_measurements(options: &Options, results: &[Measurement]) {
+    println!(
+        "{:<10} {:<10} {:>6} {:>8} {:>6} {:>12} {:>12} {:>14} {:>10} {:>10}",
+        "channel",
+        "mode",
+        "size",
+        "capacity",
+        "batch",
+        "count",
+        "elapsed",
+        "ops/s",
+        "MB/s",
+        "ns/op"
+    );
+    for m in results {
+        let capacity = match m.flavour.is_bounded() {
+            true => options.capacity.to_string(),
+            false => "-".to_string(),
+        };
+        let megabytes = m.per_second() * options.size as f64 / 1e6;
+        println!(
+            "{:<10} {:<10} {:>6} {:>8} {:>6} {:>12} {:>12.3?} {:>14.0} {:>10.1} {:>10.1}",
+            m.flavour.name(),
+            m.mode.name(),
+            options.size,
+            capacity,
+            options.batch,
+            m.count,
+            m.elapsed,
+            m.per_second(),
+            megabytes,
+            m.nanos_per_op(),
+        );
+    }
+}
+
+fn print_distributions(options: &Options, distributions: &[Distribution]) {
+    println!(
+        "{:<10} {:<10} {:>6} {:>8} {:>12} {:>10} {:>10} {:>10} {:>10}",
+        "channel", "latency", "size", "capacity", "count", "p50 ns", "p99 ns", "p99.9 ns", "max ns"
+    );
+    for d in distributions {
+        let capacity = match d.flavour.is_bounded() {
+            true => options.capacity.to_string(),
+            false => "-".to_string(),
+        };
+        for (metric, count, [p50, p99, p999, max]) in d.summaries() {
+            println!(
+                "{:<10} {:<10} {:>6} {:>8} {:>12} {:>10.0} {:>10.0} {:>10.0} {:>10.0}",
+                d.flavour.name(),
+                metric,
+                options.size,
+                capacity,
+                count,
+                p50,
+                p99,
+                p999,
+                max,
+            );
+        }
+    }
+}
+
+fn print_json(options: &Options, results: &[Measurement], distributions: &[Distribution]) {
+    let cpu = |cpu: Option<usize>| cpu.map_or("null".to_string(), |cpu| cpu.to_string());
+    let capacity = |flavour: Flavour| match flavour.is_bounded() {
+        true => options.capacity.to_string(),
+        false => "null".to_string(),
+    };
+    let mut rows: Vec<_> = results
+        .iter()
+        .map(|m| {
+            format!(
+                concat!(
+                    "  {{\"channel\": \"{}\", \"mode\": \"{}\", \"size\": {}, \"capacity\": {}, ",
+                    "\"batch\": {}, \"producer_cpu\": {}, \"consumer_cpu\": {}, \"count\": {}, ",
+                    "\"elapsed_ns\": {}, \"ops_per_sec\": {:.1}, \"ns_per_op\": {:.2}}}"
+                ),
+                m.flavour.name(),
+                m.mode.name(),
+                options.size,
+                capacity(m.flavour),
+                options.batch,
+                cpu(options.producer_cpu),
+                cpu(options.consumer_cpu),
+                m.count,
+                m.elapsed.as_nanos(),
+                m.per_second(),
+                m.nanos_per_op(),
+            )
+        })
+        .collect();
+    for d in distributions {
+        for (metric, count, [p50, p99, p999, max]) in d.summaries() {
+            rows.push(format!(
+                concat!(
+                    "  {{\"channel\": \"{}\", \"mode\": \"ping-pong\", \"latency\": \"{}\", ",
+                    "\"size\": {}, \"capacity\": {}, \"producer_cpu\": {}, \"consumer_cpu\": {}, ",
+                    "\"count\": {}, \"p50_ns\": {:.1}, \"p99_ns\": {:.1}, \"p999_ns\": {:.1}, ",
+                    "\"max_ns\": {:.1}}}"
+                ),
+                d.flavour.name(),
+                metric,
+                options.size,
+                capacity(d.flavour),
+                cpu(options.producer_cpu),
+                cpu(options.consumer_cpu),
+                count,
+                p50,
+                p99,
+                p999,
+                max,
+            ));
+        }
+    }
+    println!("[\n{}\n]", rows.join(",\n"));
+}
+
 fn main() {
-    println!("Hello, world!");
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(err) => {
+            eprintln!("spsc-bench: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let clock = Clock::new(options.clock);
+    let mut results = Vec::new();
+    let mut distributions = Vec::new();
+    for &mode in &options.modes {
+        for &flavour in &options.flavours {
+            match measure(flavour, mode, &options, clock) {
+                Report::Elapsed(measurement) => results.push(measurement),
+                Report::Latencies(distribution) => distributions.push(distribution),
+            }
+        }
+    }
+    match options.format {
+        Format::Table => print_table(&options, &results, &distributions),
+        Format::Json => print_json(&options, &results, &distributions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClockKind, Flavour, Format, Mode, Options};
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_both_value_styles() {
+        let options = parse(&["--mode=latency", "--channel", "std-mpsc", "--size", "64"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.modes, [Mode::Latency]);
+        assert_eq!(options.flavours, [Flavour::StdMpsc]);
+        assert_eq!(options.size, 64);
+        assert_eq!(options.format, Format::Table);
+        assert!(parse(&["--help"]).unwrap().is_none());
+
+        let options = parse(&["--mode", "ping-pong", "--clock=instant"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.modes, [Mode::PingPong]);
+        assert_eq!(options.clock, ClockKind::Instant);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse(&["--size", "7"]).is_err());
+        assert!(parse(&["--batch", "0"]).is_err());
+        assert!(parse(&["--capacity"]).is_err());
+        assert!(parse(&["--bogus", "1"]).is_err());
+    }
 }
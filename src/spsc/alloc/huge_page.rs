@@ -0,0 +1,273 @@
+//! An allocator that maps channel storage onto huge pages.
+//!
+//! A large ring is normally faulted in one 4 KiB page at a time as the
+//! producer first writes to it, and each fault shows up as a latency spike.
+//! [`HugePageAlloc`] maps every block directly with `mmap`, on huge pages
+//! from the `MAP_HUGETLB` pool when the system has some reserved, and
+//! otherwise on ordinary pages marked for transparent huge pages with
+//! `madvise`. It can also lock the mapping in memory and touch every page
+//! up front, so the ring never faults once the channel exists.
+
+use super::{AllocError, SpscAlloc};
+use std::alloc::Layout;
+use std::io;
+use std::ptr::{self, NonNull};
+
+/// The huge-page size every `MAP_HUGETLB` mapping asks for explicitly with
+/// `MAP_HUGE_2MB`, rather than the kernel's default size, and the granularity
+/// every mapping is rounded up to.
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// Maps each allocation onto huge pages with `mmap`.
+///
+/// Every block takes a whole number of 2 MiB huge pages, so this suits large
+/// rings such as a [bounded channel](crate::spsc::bounded_spsc)'s buffer rather
+/// than many small segments.
+///
+/// # Example
+/// ```no_run
+/// use lock_free_spsc::spsc::alloc::HugePageAlloc;
+/// use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+///
+/// let alloc = HugePageAlloc::new().pre_touch(true);
+/// let (tx, rx) = BoundedSpscChannel::try_split_in(1 << 16, alloc).unwrap();
+/// tx.send(1u64).unwrap();
+/// assert_eq!(rx.recv(), Some(1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HugePageAlloc {
+    fallback: bool,
+    lock: bool,
+    pre_touch: bool,
+}
+
+impl HugePageAlloc {
+    /// Maps blocks on `MAP_HUGETLB` pages, falling back to transparent huge
+    /// pages when none are available. Nothing is locked or pre-touched.
+    pub const fn new() -> Self {
+        HugePageAlloc {
+            fallback: true,
+            lock: false,
+            pre_touch: false,
+        }
+    }
+
+    /// Fails instead of falling back to transparent huge pages when the
+    /// `MAP_HUGETLB` pool cannot serve a block.
+    pub const fn huge_tlb_only(self) -> Self {
+        HugePageAlloc { fallback: false, ..self }
+    }
+
+    /// Locks each block in memory with `mlock`, so it is never swapped out.
+    ///
+    /// Fails if the block does not fit under `RLIMIT_MEMLOCK`.
+    pub const fn lock(self, lock: bool) -> Self {
+        HugePageAlloc { lock, ..self }
+    }
+
+    /// Writes to every page of each block when it is allocated, so that no
+    /// page faults are left for the channel's first pass over its ring.
+    pub const fn pre_touch(self, pre_touch: bool) -> Self {
+        HugePageAlloc { pre_touch, ..self }
+    }
+
+    /// Maps a block for `layout`, reporting why if it cannot.
+    ///
+    /// This is [`SpscAlloc::allocate`] with the operating system's error
+    /// kept; the block is freed through [`SpscAlloc::deallocate`] as usual.
+    pub fn map(&self, layout: Layout) -> io::Result<NonNull<u8>> {
+        if layout.align() > HUGE_PAGE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "alignment larger than a huge page",
+            ));
+        }
+        let len = mapped_len(layout)?;
+        // Huge-page mappings start on a huge page, so any supported
+        // alignment holds. Asking for 2 MiB pages keeps `len` a multiple of
+        // the page size where the default huge page is larger; a kernel
+        // without them fails the mapping and the fallback takes over.
+        let block = match map_anonymous(len, libc::MAP_HUGETLB | libc::MAP_HUGE_2MB) {
+            Ok(block) => block,
+            Err(_) if self.fallback => {
+                let block = map_aligned(len, layout.align())?;
+                // Only advice: without THP support the block still works,
+                // on ordinary pages.
+                unsafe { libc::madvise(block.as_ptr().cast(), len, libc::MADV_HUGEPAGE) };
+                block
+            }
+            Err(err) => return Err(err),
+        };
+        if self.lock && unsafe { libc::mlock(block.as_ptr().cast(), len) } != 0 {
+            let err = io::Error::last_os_error();
+            unsafe { unmap(block.as_ptr(), len) };
+            return Err(err);
+        }
+        if self.pre_touch {
+            let page = page_size();
+            for offset in (0..len).step_by(page) {
+                // Volatile, so the otherwise pointless write is not elided.
+                unsafe { ptr::write_volatile(block.as_ptr().add(offset), 0) };
+            }
+        }
+        Ok(block)
+    }
+}
+
+impl Default for HugePageAlloc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl SpscAlloc for HugePageAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.map(layout).map_err(|_| AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // The length was valid when the block was mapped.
+        let len = mapped_len(layout).unwrap_or_default();
+        // Unmapping also releases any lock on the pages.
+        unsafe { unmap(ptr.as_ptr(), len) };
+    }
+}
+
+/// Rounds `layout`'s size up to whole huge pages, the length of its mapping
+/// whichever kind of page ends up backing it.
+fn mapped_len(layout: Layout) -> io::Result<usize> {
+    layout
+        .size()
+        .max(1)
+        .checked_next_multiple_of(HUGE_PAGE_SIZE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "block too large"))
+}
+
+/// Maps `len` bytes of private, anonymous, zeroed memory.
+fn map_anonymous(len: usize, flags: libc::c_int) -> io::Result<NonNull<u8>> {
+    let block = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if block == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(NonNull::new(block.cast()).expect("mmap returned a null mapping"))
+}
+
+/// Maps `len` bytes on ordinary pages, starting at a multiple of `align`.
+///
+/// A plain mapping is only aligned to a page, so a larger alignment is met by
+/// mapping `align` bytes more and unmapping the slack on either side; what is
+/// left is exactly `len` bytes, freed like any other block.
+fn map_aligned(len: usize, align: usize) -> io::Result<NonNull<u8>> {
+    if align <= page_size() {
+        return map_anonymous(len, 0);
+    }
+    let padded = len
+        .checked_add(align)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "block too large"))?;
+    let block = map_anonymous(padded, 0)?;
+    let start = block.as_ptr() as usize;
+    let head = start.next_multiple_of(align) - start;
+    let tail = padded - head - len;
+    // Both ends are whole pages: `align`, `len` and the mapping's start are
+    // all multiples of the page size.
+    unsafe {
+        if head != 0 {
+            unmap(block.as_ptr(), head);
+        }
+        if tail != 0 {
+            unmap(block.as_ptr().add(head + len), tail);
+        }
+        Ok(NonNull::new_unchecked(block.as_ptr().add(head)))
+    }
+}
+
+/// Unmaps `len` bytes at `ptr`.
+///
+/// `munmap` only fails on a misaligned address or length, and then nothing
+/// is unmapped, so the failure would silently leak the block.
+unsafe fn unmap(ptr: *mut u8, len: usize) {
+    let ret = unsafe { libc::munmap(ptr.cast(), len) };
+    debug_assert_eq!(ret, 0, "munmap failed: {}", io::Error::last_os_error());
+}
+
+fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+// The tests call into the kernel, which Miri cannot follow.
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::{HUGE_PAGE_SIZE, HugePageAlloc, map_aligned, mapped_len};
+    use crate::spsc::alloc::SpscAlloc;
+    use crate::spsc::bounded_spsc::BoundedSpscChannel;
+    use std::alloc::Layout;
+
+    #[test]
+    fn maps_pre_touched_ring() {
+        let alloc = HugePageAlloc::new().pre_touch(true);
+        let (tx, rx) = BoundedSpscChannel::try_split_in(100_000, alloc).unwrap();
+        (&tx).extend(0..100_000u64);
+        assert!(rx.try_iter().eq(0..100_000));
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_map() {
+        let alloc = HugePageAlloc::new();
+        let aligned = Layout::from_size_align(64, 2 * HUGE_PAGE_SIZE).unwrap();
+        assert!(alloc.map(aligned).is_err());
+        let huge = Layout::from_size_align(isize::MAX as usize - 4096, 1).unwrap();
+        assert!(alloc.allocate(huge).is_err());
+        // Without a reserved pool this fails, and with one it succeeds; it
+        // never panics.
+        let layout = Layout::array::<u64>(1024).unwrap();
+        if let Ok(block) = alloc.huge_tlb_only().allocate(layout) {
+            unsafe { alloc.deallocate(block, layout) };
+        }
+    }
+
+    #[test]
+    fn fallback_honours_large_alignment() {
+        for align in [8192, 64 * 1024, HUGE_PAGE_SIZE] {
+            let layout = Layout::from_size_align(3 * 4096, align).unwrap();
+            let len = mapped_len(layout).unwrap();
+            let block = map_aligned(len, align).unwrap();
+            assert_eq!(block.as_ptr() as usize % align, 0);
+            unsafe {
+                block.as_ptr().add(len - 1).write(1);
+                HugePageAlloc::new().deallocate(block, layout);
+            }
+        }
+        // Through the allocator, whichever path serves it.
+        let layout = Layout::from_size_align(64, 1024 * 1024).unwrap();
+        let block = HugePageAlloc::new().map(layout).unwrap();
+        assert_eq!(block.as_ptr() as usize % layout.align(), 0);
+        unsafe { HugePageAlloc::new().deallocate(block, layout) };
+    }
+
+    #[test]
+    fn locked_block_is_usable() {
+        let layout = Layout::array::<u8>(4096).unwrap();
+        // `RLIMIT_MEMLOCK` may be too low for a huge page; that is an error,
+        // not a panic.
+        let Ok(block) = HugePageAlloc::new().lock(true).map(layout) else {
+            return;
+        };
+        unsafe {
+            block.as_ptr().write_bytes(0xAB, layout.size());
+            assert_eq!(*block.as_ptr().add(4095), 0xAB);
+            HugePageAlloc::new().deallocate(block, layout);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Pluggable allocators for channel storage.
+//!
+//! The bounded ring and the unbounded queue's segments get their memory from
+//! an [`SpscAlloc`], carried by every channel handle as a generic parameter
+//! that defaults to [`Global`], the global allocator. Supplying another one
+//! places that storage in an arena, a pool, or anything else that can hand
+//! out blocks of a given [`Layout`]; the channels ask for memory when they
+//! are created, and the unbounded one again whenever it links a segment.
+//!
+//! On Linux, [`HugePageAlloc`] maps rings onto huge pages and can lock and
+//! pre-fault them, for rings large enough that first-touch page faults show
+//! up as latency.
+//!
+//! # Example
+//! ```
+//! use lock_free_spsc::spsc::alloc::RecordingAlloc;
+//! use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+//! use std::sync::Arc;
+//!
+//! let alloc = Arc::new(RecordingAlloc::new());
+//! let (tx, rx) = BoundedSpscChannel::split_in(4, Arc::clone(&alloc));
+//! tx.send(1).unwrap();
+//! assert_eq!(rx.recv(), Some(1));
+//! assert_eq!(alloc.allocations(), 1);
+//!
+//! drop((tx, rx));
+//! assert_eq!(alloc.frees(), 1);
+//! assert_eq!(alloc.live_bytes(), 0);
+//! ```
+
+#[cfg(target_os = "linux")]
+mod huge_page;
+
+#[cfg(target_os = "linux")]
+pub use huge_page::HugePageAlloc;
+
+use std::alloc::Layout;
+use std::error::Error;
+use std::fmt;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The error returned when an [`SpscAlloc`] cannot satisfy a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl Error for AllocError {}
+
+/// A source of memory for channel storage.
+///
+/// Both halves of a channel may allocate and free through the same
+/// allocator, each on its own thread, hence `Send + Sync`.
+///
+/// # Safety
+/// A block returned by [`allocate`](Self::allocate) must be valid for reads
+/// and writes of `layout.size()` bytes, aligned to `layout.align()`, and stay
+/// so until it is passed to [`deallocate`](Self::deallocate). The channels
+/// never ask for zero-sized blocks.
+pub unsafe trait SpscAlloc: Send + Sync {
+    /// Allocates a block of memory fitting `layout`.
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+    /// Frees a block.
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by [`allocate`](Self::allocate) on this
+    /// allocator with the same `layout`, and not freed since.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+/// The global allocator, used unless a channel is given another one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Global;
+
+unsafe impl SpscAlloc for Global {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        debug_assert_ne!(layout.size(), 0);
+        NonNull::new(unsafe { std::alloc::alloc(layout) }).ok_or(AllocError)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) }
+    }
+}
+
+unsafe impl<A: SpscAlloc + ?Sized> SpscAlloc for &A {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        (**self).allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { (**self).deallocate(ptr, layout) }
+    }
+}
+
+unsafe impl<A: SpscAlloc + ?Sized> SpscAlloc for Arc<A> {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        (**self).allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { (**self).deallocate(ptr, layout) }
+    }
+}
+
+/// One call a [`RecordingAlloc`] saw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocEvent {
+    /// A block of this layout was allocated.
+    Alloc(Layout),
+    /// A block of this layout was freed.
+    Free(Layout),
+}
+
+/// An allocator for tests that forwards to [`Global`] and records every
+/// allocation and free made through it.
+///
+/// Share it with a channel through an [`Arc`] or a reference to inspect the
+/// record afterwards.
+#[derive(Debug, Default)]
+pub struct RecordingAlloc {
+    events: Mutex<Vec<AllocEvent>>,
+}
+
+impl RecordingAlloc {
+    /// Creates an allocator with an empty record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns every allocation and free so far, oldest first.
+    pub fn events(&self) -> Vec<AllocEvent> {
+        self.record().clone()
+    }
+
+    /// Returns the number of blocks allocated so far.
+    pub fn allocations(&self) -> usize {
+        let record = self.record();
+        record.iter().filter(|event| matches!(event, AllocEvent::Alloc(_))).count()
+    }
+
+    /// Returns the number of blocks freed so far.
+    pub fn frees(&self) -> usize {
+        let record = self.record();
+        record.iter().filter(|event| matches!(event, AllocEvent::Free(_))).count()
+    }
+
+    /// Returns the bytes allocated and not yet freed.
+    pub fn live_bytes(&self) -> usize {
+        self.record().iter().fold(0, |live, event| match event {
+            AllocEvent::Alloc(layout) => live + layout.size(),
+            AllocEvent::Free(layout) => live - layout.size(),
+        })
+    }
+
+    fn record(&self) -> MutexGuard<'_, Vec<AllocEvent>> {
+        // A panic elsewhere while holding the lock leaves the record intact.
+        self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+unsafe impl SpscAlloc for RecordingAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = Global.allocate(layout)?;
+        self.record().push(AllocEvent::Alloc(layout));
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.record().push(AllocEvent::Free(layout));
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AllocEvent, RecordingAlloc};
+    use crate::spsc::bounded_spsc::BoundedSpscChannel;
+    use crate::spsc::unbounded_spsc::UnboundSpscChannel;
+    use std::alloc::Layout;
+
+    #[test]
+    fn bounded_ring_comes_from_the_allocator() {
+        let alloc = RecordingAlloc::new();
+        let (tx, rx) = BoundedSpscChannel::split_in(3, &alloc);
+        (0..3).for_each(|i| tx.send(i).unwrap());
+        assert!(rx.try_iter().eq(0..3));
+        drop((tx, rx));
+        // One slot more than the capacity, allocated once and freed once.
+        let ring = Layout::array::<u32>(4).unwrap();
+        assert_eq!(alloc.events(), [AllocEvent::Alloc(ring), AllocEvent::Free(ring)]);
+    }
+
+    #[test]
+    fn zero_sized_ring_allocates_nothing() {
+        let alloc = RecordingAlloc::new();
+        let (tx, rx) = BoundedSpscChannel::split_in::<(), _>(3, &alloc);
+        tx.send(()).unwrap();
+        drop((tx, rx));
+        assert!(alloc.events().is_empty());
+    }
+
+    #[test]
+    fn unbounded_segments_come_from_the_allocator() {
+        let alloc = RecordingAlloc::new();
+        let (tx, rx) = UnboundSpscChannel::split_in(&alloc);
+        // A segment is a header and a buffer.
+        assert_eq!(alloc.allocations(), 2);
+        assert_eq!(alloc.live_bytes(), rx.usage().bytes);
+
+        (&tx).extend(0..1000);
+        assert_eq!(alloc.live_bytes(), tx.usage().bytes);
+        assert!(rx.try_iter().eq(0..1000));
+        rx.shrink_to_fit();
+        assert_eq!(alloc.live_bytes(), rx.usage().bytes);
+        assert_eq!(alloc.allocations() - alloc.frees(), 2);
+
+        drop((tx, rx));
+        assert_eq!(alloc.allocations(), alloc.frees());
+        assert_eq!(alloc.live_bytes(), 0);
+    }
+}
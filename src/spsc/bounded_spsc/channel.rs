@@ -23,9 +23,28 @@
 //! assert_eq!(rx.recv(), Some(42));
 //! ```
 //!
+//! # Thread safety
+//! The halves can only be sent to another thread when `T: Send`:
+//!
+//! ```compile_fail
+//! use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+//! use std::rc::Rc;
+//!
+//! let (tx, _rx) = BoundedSpscChannel::split::<Rc<u8>>(4);
+//! std::thread::spawn(move || drop(tx));
+//! ```
+//!
+//! ```compile_fail
+//! use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+//! use std::rc::Rc;
+//!
+//! let (_tx, rx) = BoundedSpscChannel::split::<Rc<u8>>(4);
+//! std::thread::spawn(move || drop(rx));
+//! ```
+//!
 //! # Internals
 //! Internally, the implementation wraps a [`BoundedSpsc<T>`] in an `Arc`
-//! so that the producer (`Sender<T>`) and consumer (`Receiver<T>`) can safely
+//! so that the producer (`Sender<T, O>`) and consumer (`Receiver<T, O>`) can safely
 //! share the same buffer. Operations are wait-free under ideal conditions and
 //! make use of memory ordering for correct synchronization between threads.
 //!
@@ -33,96 +52,616 @@
 //! wraparound, index updates, and buffer safety are handled.
 
 use super::inner_spsc::BoundedSpsc;
+use crate::spsc::alloc::{Global, SpscAlloc};
+use crate::spsc::builder::{Options, Overflow};
+use crate::spsc::error::{ChannelError, ReuniteError};
+#[cfg(feature = "metrics")]
+use crate::spsc::metrics::{self, ChannelStats};
+use crate::spsc::metrics::{ConsumerCounters, ProducerCounters};
+use crate::spsc::observer::{NoopObserver, Side, SpscObserver};
+use crate::spsc::select::sealed;
+use crate::spsc::waiter::{WaitSlot, wait_with};
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
 use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering::{Acquire, Release},
+};
 
 /// Entry point for splitting a bounded SPSC channel into its sender and receiver halves.
 pub struct BoundedSpscChannel;
 
+/// The two halves of a channel, as returned by the constructors.
+type Halves<T, O, A> = (Sender<T, O, A>, Receiver<T, O, A>);
+
 impl BoundedSpscChannel {
     /// Creates a bounded channel with the specified capacity.
     ///
     /// Returns a pair of [`Sender`] and [`Receiver`] handles that share
     /// the same underlying buffer. Capacity must be greater than 0.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero, or if the ring cannot be allocated; use
+    /// [`try_split`](Self::try_split) to handle those cases.
     pub fn split<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
-        let inner = BoundedSpsc::new(capacity);
+        Self::split_with_observer(capacity, NoopObserver)
+    }
+
+    /// Like [`split`](Self::split), but returns a [`ChannelError`] instead of
+    /// panicking.
+    ///
+    /// # Example
+    /// ```
+    /// use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+    /// use lock_free_spsc::spsc::error::ChannelError;
+    ///
+    /// let (tx, rx) = BoundedSpscChannel::try_split(4).unwrap();
+    /// tx.send(1).unwrap();
+    /// assert_eq!(rx.recv(), Some(1));
+    ///
+    /// let err = BoundedSpscChannel::try_split::<u8>(0).err();
+    /// assert_eq!(err, Some(ChannelError::ZeroCapacity));
+    /// ```
+    pub fn try_split<T>(capacity: usize) -> Result<Halves<T, NoopObserver, Global>, ChannelError> {
+        let queue = BoundedSpsc::try_new(capacity)?;
+        Ok(Self::from_queue(queue, NoopObserver, Options::default()))
+    }
+
+    /// Creates a bounded channel that reports its events to `observer`.
+    ///
+    /// See [`observer`](crate::spsc::observer) for the available hooks.
+    pub fn split_with_observer<T, O: SpscObserver>(
+        capacity: usize,
+        observer: O,
+    ) -> (Sender<T, O>, Receiver<T, O>) {
+        Self::from_queue(BoundedSpsc::new(capacity), observer, Options::default())
+    }
+
+    /// Creates a bounded channel whose ring is allocated from `alloc`.
+    ///
+    /// See [`alloc`](crate::spsc::alloc) for the allocator interface.
+    pub fn split_in<T, A: SpscAlloc>(
+        capacity: usize,
+        alloc: A,
+    ) -> (Sender<T, NoopObserver, A>, Receiver<T, NoopObserver, A>) {
+        Self::from_queue(BoundedSpsc::new_in(capacity, alloc), NoopObserver, Options::default())
+    }
+
+    /// Like [`split_in`](Self::split_in), but returns a [`ChannelError`]
+    /// instead of panicking, for instance when `alloc` cannot provide the
+    /// ring.
+    pub fn try_split_in<T, A: SpscAlloc>(
+        capacity: usize,
+        alloc: A,
+    ) -> Result<Halves<T, NoopObserver, A>, ChannelError> {
+        let queue = BoundedSpsc::try_new_in(capacity, alloc)?;
+        Ok(Self::from_queue(queue, NoopObserver, Options::default()))
+    }
+
+    pub(crate) fn from_queue<T, O: SpscObserver, A: SpscAlloc>(
+        queue: BoundedSpsc<T, A>,
+        observer: O,
+        options: Options,
+    ) -> (Sender<T, O, A>, Receiver<T, O, A>) {
+        let inner = Shared {
+            queue,
+            tx_slot: WaitSlot::new(),
+            rx_slot: WaitSlot::new(),
+            tx_closed: AtomicBool::new(false),
+            rx_closed: AtomicBool::new(false),
+            producer: ProducerCounters::new(options.metrics),
+            consumer: ConsumerCounters::new(options.metrics),
+            observer,
+            options,
+        };
         let sender = Sender {
             inner: Arc::new(inner),
+            _not_sync: PhantomData,
         };
         let receiver = Receiver {
             inner: Arc::clone(&sender.inner),
+            _not_sync: PhantomData,
         };
         (sender, receiver)
     }
 }
 
+/// State shared by both halves: the ring buffer, the slots a blocked sender
+/// or receiver parks on, whether each half has been dropped, each half's
+/// metrics counters, the observer, and the options it was built with.
+struct Shared<T, O, A: SpscAlloc> {
+    queue: BoundedSpsc<T, A>,
+    tx_slot: WaitSlot,
+    rx_slot: WaitSlot,
+    tx_closed: AtomicBool,
+    rx_closed: AtomicBool,
+    producer: ProducerCounters,
+    consumer: ConsumerCounters,
+    observer: O,
+    options: Options,
+}
+
+impl<T, O, A: SpscAlloc> Shared<T, O, A> {
+    #[cfg(feature = "metrics")]
+    fn stats(&self) -> ChannelStats {
+        metrics::snapshot(&self.producer, &self.consumer)
+    }
+}
+
 /// The sending half of a bounded SPSC channel.
 ///
-/// This type is cloneable and allows sending values into the queue.
 /// It fails with the original value if the buffer is full.
-pub struct Sender<T> {
-    inner: Arc<BoundedSpsc<T>>,
+///
+/// A `Sender` can be moved to another thread but not shared with one, so
+/// there is never more than one producer:
+///
+/// ```compile_fail
+/// use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+/// use std::sync::Arc;
+///
+/// let (tx, _rx) = BoundedSpscChannel::split::<u8>(4);
+/// let tx = Arc::new(tx);
+/// let second = Arc::clone(&tx);
+/// std::thread::spawn(move || second.send(1));
+/// ```
+pub struct Sender<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    inner: Arc<Shared<T, O, A>>,
+    /// `!Sync`: the queue allows a single producer thread.
+    _not_sync: PhantomData<Cell<()>>,
 }
 
-impl<T> Sender<T> {
+impl<T, O: SpscObserver, A: SpscAlloc> Sender<T, O, A> {
     /// Attempts to send a value into the channel.
     ///
-    /// Returns `Err(value)` if the buffer is full.
+    /// Returns `Err(value)` if the buffer is full, unless the channel was
+    /// built to [block on overflow](Overflow::Block); then this is
+    /// [`send_blocking`](Self::send_blocking).
     #[inline(always)]
     pub fn send(&self, value: T) -> Result<(), T> {
-        self.inner.push(value)
+        if self.inner.options.overflow == Overflow::Block {
+            return self.send_blocking(value);
+        }
+        self.push(value)
+    }
+
+    /// Sends a value if the buffer has room, without blocking.
+    #[inline(always)]
+    fn push(&self, value: T) -> Result<(), T> {
+        // The sender is neither `Clone` nor `Sync`, so it is the only producer.
+        if let Err(value) = unsafe { self.inner.queue.push(value) } {
+            self.inner.producer.full();
+            self.inner.observer.on_full();
+            return Err(value);
+        }
+        self.inner.producer.sent(|| self.inner.queue.len());
+        self.inner.observer.on_send();
+        self.inner.rx_slot.notify();
+        Ok(())
+    }
+
+    /// Sends a value, parking the thread while the buffer is full.
+    ///
+    /// Returns `Err(value)` if the [`Receiver`] has been dropped, since the
+    /// value could never be received.
+    pub fn send_blocking(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        let parked = |duration| self.inner.producer.parked(duration);
+        wait_with(self.inner.options.wait, &[&self.inner.tx_slot], parked, || {
+            let pending = value.take()?;
+            if self.inner.rx_closed.load(Acquire) {
+                return Some(Err(pending));
+            }
+            match self.push(pending) {
+                Ok(()) => Some(Ok(())),
+                Err(pending) => {
+                    value = Some(pending);
+                    None
+                }
+            }
+        })
+    }
+
+    /// Returns `true` if the [`Receiver`] has been dropped.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.rx_closed.load(Acquire)
+    }
+
+    /// Returns the observer attached to the channel.
+    pub fn observer(&self) -> &O {
+        &self.inner.observer
+    }
+
+    /// Returns the name the channel was [built](crate::spsc::builder) with.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.options.name.as_deref()
+    }
+
+    /// Returns a snapshot of the channel's counters.
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> ChannelStats {
+        self.inner.stats()
     }
 
     /// Returns `true` if the channel is currently full.
     #[inline(always)]
     pub fn is_full(&self) -> bool {
-        self.inner.is_full()
+        self.inner.queue.is_full()
     }
 
-    /// Returns the capacity of the channel.
+    /// Returns how many values the channel can hold.
     #[inline(always)]
     pub fn capacity(&self) -> usize {
-        self.inner.capacity()
+        self.inner.queue.capacity()
     }
 
     /// Returns `true` if the channel is empty.
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+        self.inner.queue.is_empty()
+    }
+
+    /// Returns the number of values currently in the channel.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.inner.queue.len()
+    }
+
+    /// Puts the two halves of a channel back together, so that the values
+    /// still queued in it can be taken out instead of dropped.
+    ///
+    /// Fails, handing both halves back, if `rx` belongs to another channel.
+    ///
+    /// # Example
+    /// ```
+    /// use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+    ///
+    /// let (tx, rx) = BoundedSpscChannel::split(4);
+    /// (&tx).extend(["a", "b", "c"]);
+    /// assert_eq!(rx.recv(), Some("a"));
+    ///
+    /// let undelivered = tx.reunite(rx).unwrap().into_vec();
+    /// assert_eq!(undelivered, ["b", "c"]);
+    /// ```
+    pub fn reunite(self, rx: Receiver<T, O, A>) -> Result<Channel<T, O, A>, Mismatch<T, O, A>> {
+        if !Arc::ptr_eq(&self.inner, &rx.inner) {
+            return Err(ReuniteError(self, rx));
+        }
+        drop(rx.into_shared());
+        match Arc::into_inner(self.into_shared()) {
+            Some(shared) => Ok(Channel { shared }),
+            None => unreachable!("both halves of the channel were given up"),
+        }
+    }
+
+    /// Gives up the sender's share of the channel without closing it.
+    fn into_shared(self) -> Arc<Shared<T, O, A>> {
+        let this = ManuallyDrop::new(self);
+        // `this` is never used or dropped again, so the `Arc` moves out once.
+        unsafe { ptr::read(&this.inner) }
     }
 }
 
+/// The error returned by [`Sender::reunite`].
+type Mismatch<T, O, A> = ReuniteError<Sender<T, O, A>, Receiver<T, O, A>>;
+
 /// The receiving half of a bounded SPSC channel.
 ///
-/// This type is cloneable and allows receiving values from the queue.
 /// It returns `None` when the buffer is empty.
-pub struct Receiver<T> {
-    inner: Arc<BoundedSpsc<T>>,
+///
+/// Like [`Sender`], a `Receiver` can be moved to another thread but not
+/// shared with one.
+pub struct Receiver<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    inner: Arc<Shared<T, O, A>>,
+    /// `!Sync`: the queue allows a single consumer thread.
+    _not_sync: PhantomData<Cell<()>>,
 }
 
-impl<T> Receiver<T> {
+impl<T, O: SpscObserver, A: SpscAlloc> Receiver<T, O, A> {
     /// Attempts to receive a value from the channel.
     ///
     /// Returns `None` if the buffer is empty.
     #[inline(always)]
     pub fn recv(&self) -> Option<T> {
-        self.inner.pop()
+        // The receiver is neither `Clone` nor `Sync`, so it is the only consumer.
+        let Some(value) = (unsafe { self.inner.queue.pop() }) else {
+            self.inner.consumer.empty();
+            self.inner.observer.on_empty_poll();
+            return None;
+        };
+        self.inner.consumer.received();
+        self.inner.observer.on_recv();
+        // Only a relaxed load of the slot's armed flag unless the sender is
+        // parked in `send_blocking`.
+        self.inner.tx_slot.notify();
+        Some(value)
+    }
+
+    /// Receives a value, parking the thread while the buffer is empty.
+    ///
+    /// Returns `None` once the [`Sender`] has been dropped and every value it
+    /// sent has been received.
+    pub fn recv_blocking(&self) -> Option<T> {
+        let parked = |duration| self.inner.consumer.parked(duration);
+        let wait = self.inner.options.wait;
+        wait_with(wait, &[&self.inner.rx_slot], parked, || match self.recv() {
+            Some(value) => Some(Some(value)),
+            // The sender publishes everything before closing, so look once more.
+            None if self.is_disconnected() => Some(self.recv()),
+            None => None,
+        })
+    }
+
+    /// Returns an iterator over the values currently in the channel.
+    ///
+    /// The iterator stops as soon as the buffer is empty.
+    pub fn try_iter(&self) -> TryIter<'_, T, O, A> {
+        TryIter { receiver: self }
+    }
+
+    /// Returns an iterator that blocks for each value and stops once the
+    /// [`Sender`] has been dropped and the buffer is drained.
+    pub fn iter(&self) -> Iter<'_, T, O, A> {
+        Iter { receiver: self }
+    }
+
+    /// Returns `true` if the [`Sender`] has been dropped.
+    ///
+    /// Values sent before the disconnection may still be waiting in the buffer.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.tx_closed.load(Acquire)
+    }
+
+    /// Returns the observer attached to the channel.
+    pub fn observer(&self) -> &O {
+        &self.inner.observer
+    }
+
+    /// Returns the name the channel was [built](crate::spsc::builder) with.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.options.name.as_deref()
+    }
+
+    /// Returns a snapshot of the channel's counters.
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> ChannelStats {
+        self.inner.stats()
     }
 
     /// Returns `true` if the channel is full.
     #[inline(always)]
     pub fn is_full(&self) -> bool {
-        self.inner.is_full()
+        self.inner.queue.is_full()
     }
 
-    /// Returns the capacity of the channel.
+    /// Returns how many values the channel can hold.
     #[inline(always)]
     pub fn capacity(&self) -> usize {
-        self.inner.capacity()
+        self.inner.queue.capacity()
     }
 
     /// Returns `true` if the channel is empty.
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+        self.inner.queue.is_empty()
+    }
+
+    /// Returns the number of values currently in the channel.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.inner.queue.len()
+    }
+
+    /// Returns a reference to the next value without receiving it.
+    ///
+    /// Takes `&mut self` so the value cannot be received while borrowed.
+    #[inline(always)]
+    pub fn peek(&mut self) -> Option<&T> {
+        // Only the receiver pops, and the exclusive borrow keeps it from doing so.
+        unsafe { self.inner.queue.peek() }
+    }
+
+    /// Gives up the receiver's share of the channel without closing it.
+    fn into_shared(self) -> Arc<Shared<T, O, A>> {
+        let this = ManuallyDrop::new(self);
+        // `this` is never used or dropped again, so the `Arc` moves out once.
+        unsafe { ptr::read(&this.inner) }
+    }
+}
+
+/// A bounded channel whose halves were put back together by
+/// [`Sender::reunite`].
+///
+/// It owns the values that were sent and never received: take them out with
+/// [`drain`](Self::drain) or [`into_vec`](Self::into_vec), to persist them
+/// during a shutdown for instance. Whatever is left is dropped with it.
+pub struct Channel<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    shared: Shared<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Channel<T, O, A> {
+    /// Returns an iterator that takes the values out of the channel, oldest
+    /// first. Values it does not reach stay in the channel.
+    pub fn drain(&mut self) -> Drain<'_, T, O, A> {
+        Drain { channel: self }
+    }
+
+    /// Takes every value out of the channel, oldest first.
+    pub fn into_vec(mut self) -> Vec<T> {
+        let mut values = Vec::with_capacity(self.len());
+        values.extend(self.drain());
+        values
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.queue.len()
+    }
+
+    /// Returns `true` if the channel holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.shared.queue.is_empty()
+    }
+
+    /// Returns how many values the channel can hold.
+    pub fn capacity(&self) -> usize {
+        self.shared.queue.capacity()
+    }
+
+    /// Returns the name the channel was [built](crate::spsc::builder) with.
+    pub fn name(&self) -> Option<&str> {
+        self.shared.options.name.as_deref()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> fmt::Debug for Channel<T, O, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("name", &self.name())
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator that takes the values out of a reunited bounded channel.
+///
+/// Created by [`Channel::drain`].
+pub struct Drain<'a, T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    channel: &'a mut Channel<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for Drain<'_, T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        // The channel owns the queue outright, so it is the only consumer.
+        unsafe { self.channel.shared.queue.pop() }
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> fmt::Debug for Sender<T, O, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("name", &self.name())
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> fmt::Debug for Receiver<T, O, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("name", &self.name())
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Drop for Sender<T, O, A> {
+    fn drop(&mut self) {
+        self.inner.tx_closed.store(true, Release);
+        self.inner.rx_slot.notify();
+        self.inner.observer.on_disconnect(Side::Sender);
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Drop for Receiver<T, O, A> {
+    fn drop(&mut self) {
+        self.inner.rx_closed.store(true, Release);
+        self.inner.tx_slot.notify();
+        self.inner.observer.on_disconnect(Side::Receiver);
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Extend<T> for &Sender<T, O, A> {
+    /// Sends every item with [`Sender::send_blocking`].
+    ///
+    /// Stops early if the [`Receiver`] is dropped; the item that could not be
+    /// delivered is dropped and the rest of the iterator is left untouched.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            if self.send_blocking(value).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// An iterator over the values currently in a bounded channel.
+///
+/// Created by [`Receiver::try_iter`].
+pub struct TryIter<'a, T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    receiver: &'a Receiver<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for TryIter<'_, T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv()
+    }
+}
+
+/// A blocking iterator over the values of a bounded channel.
+///
+/// Created by [`Receiver::iter`].
+pub struct Iter<'a, T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    receiver: &'a Receiver<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for Iter<'_, T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv_blocking()
+    }
+}
+
+/// An owning blocking iterator over the values of a bounded channel.
+///
+/// Created by the [`IntoIterator`] implementation of [`Receiver`].
+pub struct IntoIter<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    receiver: Receiver<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for IntoIter<T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv_blocking()
+    }
+}
+
+impl<'a, T, O: SpscObserver, A: SpscAlloc> IntoIterator for &'a Receiver<T, O, A> {
+    type Item = T;
+    type IntoIter = Iter<'a, T, O, A>;
+
+    fn into_iter(self) -> Iter<'a, T, O, A> {
+        self.iter()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> IntoIterator for Receiver<T, O, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, O, A>;
+
+    fn into_iter(self) -> IntoIter<T, O, A> {
+        IntoIter { receiver: self }
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> sealed::Selectable for Receiver<T, O, A> {
+    fn is_ready(&self) -> bool {
+        !self.inner.queue.is_empty() || self.is_disconnected()
+    }
+
+    fn rx_slot(&self) -> &WaitSlot {
+        &self.inner.rx_slot
     }
 }
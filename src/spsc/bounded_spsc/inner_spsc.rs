@@ -1,12 +1,13 @@
 use crate::cache_padded::CachePadded;
-use std::alloc::{Layout, alloc, dealloc};
+use crate::spsc::alloc::{Global, SpscAlloc};
+use crate::spsc::error::ChannelError;
+use crate::spsc::drop_guard::for_each_unwinding;
+use crate::sync::{AtomicUsize, UnsafeCell};
+use std::alloc::Layout;
 use std::{
     mem::MaybeUninit,
-    ptr::NonNull,
-    sync::atomic::{
-        AtomicUsize,
-        Ordering::{Acquire, Relaxed, Release},
-    },
+    ptr::{self, NonNull},
+    sync::atomic::Ordering::{Acquire, Relaxed, Release},
 };
 
 /// This struct will be wrapped by the [`super::channel::BoundedSpscChannel`] module as a
@@ -52,27 +53,55 @@ use std::{
 /// # Thread safety
 ///
 /// Supports exactly one producer and one consumer thread concurrently.
+/// Nothing here enforces that, so [`push`](Self::push) and [`pop`](Self::pop)
+/// are `unsafe`; the channel handles uphold it by being `!Sync` and not
+/// `Clone`.
 ///
 /// # Example
 ///
 /// See the [`super::channel::BoundedSpscChannel`] module for usage examples.
-pub(crate) struct BoundedSpsc<T> {
+pub(crate) struct BoundedSpsc<T, A: SpscAlloc = Global> {
     next_head: CachePadded<AtomicUsize>,
     tail: CachePadded<AtomicUsize>,
-    buffer: Array<T>,
+    buffer: Array<T, A>,
 }
 
-struct Array<T> {
-    buffer: NonNull<MaybeUninit<T>>,
+type Slot<T> = UnsafeCell<MaybeUninit<T>>;
+
+struct Array<T, A: SpscAlloc> {
+    buffer: NonNull<Slot<T>>,
     capacity: usize,
+    alloc: A,
 }
 
-impl<T> Array<T> {
-    fn new(capacity: usize) -> Self {
-        let layout = Layout::array::<MaybeUninit<T>>(capacity).expect("Invalid layout");
-        let ptr = unsafe { alloc(layout) as *mut MaybeUninit<T> };
-        let buffer = NonNull::new(ptr).expect("Failed to allocate memory");
-        Self { buffer, capacity }
+impl<T, A: SpscAlloc> Array<T, A> {
+    /// Allocates `capacity` slots from `alloc`.
+    ///
+    /// Zero-sized slots need no memory: the buffer is then a dangling pointer
+    /// and only the indices of the ring carry information.
+    fn try_new_in(capacity: usize, alloc: A) -> Result<Self, ChannelError> {
+        let layout =
+            Layout::array::<Slot<T>>(capacity).map_err(|_| ChannelError::LayoutOverflow)?;
+        let buffer = match layout.size() {
+            0 => NonNull::dangling(),
+            _ => alloc.allocate(layout)?,
+        };
+        Ok(unsafe { Self::from_raw_parts(buffer.cast(), capacity, alloc) })
+    }
+
+    /// Takes ownership of a buffer of `capacity` slots and initializes them.
+    ///
+    /// # Safety
+    /// `buffer` must be dangling if the slots are zero-sized, and otherwise
+    /// come from `alloc` with the layout of `capacity` slots.
+    unsafe fn from_raw_parts(buffer: NonNull<Slot<T>>, capacity: usize, alloc: A) -> Self {
+        if size_of::<Slot<T>>() != 0 {
+            for index in 0..capacity {
+                // A no-op for `std`'s cell; loom's cell carries tracking state.
+                unsafe { buffer.as_ptr().add(index).write(UnsafeCell::new(MaybeUninit::uninit())) };
+            }
+        }
+        Self { buffer, capacity, alloc }
     }
 
     /// Inserts a value at `index` in the buffer.
@@ -83,8 +112,8 @@ impl<T> Array<T> {
     #[inline(always)]
     pub(crate) unsafe fn insert(&self, index: usize, value: T) {
         unsafe {
-            let ptr = self.buffer.as_ptr().add(index);
-            ptr.write(MaybeUninit::new(value));
+            let slot = &*self.buffer.as_ptr().add(index);
+            slot.with_mut(|ptr| ptr.write(MaybeUninit::new(value)));
         }
     }
 
@@ -96,17 +125,49 @@ impl<T> Array<T> {
     #[inline(always)]
     pub(crate) unsafe fn get(&self, index: usize) -> T {
         unsafe {
-            let ptr = self.buffer.as_ptr().add(index);
-            (*ptr).assume_init_read()
+            let slot = &*self.buffer.as_ptr().add(index);
+            slot.with(|ptr| (*ptr).assume_init_read())
+        }
+    }
+
+    /// Returns a reference to the value at `index`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee the slot at `index` is initialized and stays
+    /// untouched for as long as the reference is alive.
+    #[inline(always)]
+    pub(crate) unsafe fn get_ref(&self, index: usize) -> &T {
+        unsafe {
+            let slot = &*self.buffer.as_ptr().add(index);
+            &*slot.with(|ptr| (*ptr).as_ptr())
+        }
+    }
+
+    /// Drops the value at `index` in place.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee the slot at `index` is initialized, and must
+    /// not read it again afterwards.
+    #[inline(always)]
+    unsafe fn drop_in_place(&self, index: usize) {
+        unsafe {
+            let slot = &*self.buffer.as_ptr().add(index);
+            slot.with_mut(|ptr| (*ptr).assume_init_drop());
         }
     }
 }
 
-impl<T> Drop for Array<T> {
+impl<T, A: SpscAlloc> Drop for Array<T, A> {
     fn drop(&mut self) {
         unsafe {
-            let layout = Layout::array::<MaybeUninit<T>>(self.capacity).unwrap();
-            dealloc(self.buffer.as_ptr() as *mut u8, layout);
+            let slots = ptr::slice_from_raw_parts_mut(self.buffer.as_ptr(), self.capacity);
+            ptr::drop_in_place(slots);
+            let layout = Layout::array::<Slot<T>>(self.capacity).unwrap();
+            if layout.size() != 0 {
+                self.alloc.deallocate(self.buffer.cast(), layout);
+            }
         }
     }
 }
@@ -131,16 +192,42 @@ impl<T> BoundedSpsc<T> {
     /// One slot is always left unused so that head == tail means empty,
     /// and (head + 1) % capacity == tail means full, avoiding ambiguity.
     /// This simplifies the lock-free design with just two atomic indices.
-
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero or the ring cannot be allocated; see
+    /// [`try_new`](Self::try_new).
     pub(crate) fn new(capacity: usize) -> Self {
-        let buffer = Array::new(capacity + 1);
+        Self::new_in(capacity, Global)
+    }
+
+    /// Like [`new`](Self::new), but returns an error instead of panicking.
+    pub(crate) fn try_new(capacity: usize) -> Result<Self, ChannelError> {
+        Self::try_new_in(capacity, Global)
+    }
+}
+
+impl<T, A: SpscAlloc> BoundedSpsc<T, A> {
+    /// Creates a new `BoundedSpsc` queue whose ring is allocated from `alloc`.
+    ///
+    /// See [`new`](BoundedSpsc::new) for how `capacity` is used.
+    pub(crate) fn new_in(capacity: usize, alloc: A) -> Self {
+        Self::try_new_in(capacity, alloc).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`new_in`](Self::new_in), but returns an error instead of panicking.
+    pub(crate) fn try_new_in(capacity: usize, alloc: A) -> Result<Self, ChannelError> {
+        if capacity == 0 {
+            return Err(ChannelError::ZeroCapacity);
+        }
+        let slots = capacity.checked_add(1).ok_or(ChannelError::LayoutOverflow)?;
+        let buffer = Array::try_new_in(slots, alloc)?;
         let next_head = CachePadded::new(AtomicUsize::new(0));
         let tail = CachePadded::new(AtomicUsize::new(0));
-        Self {
+        Ok(Self {
             next_head,
             tail,
             buffer,
-        }
+        })
     }
 
     /// Attempts to push a value into the queue.
@@ -158,8 +245,13 @@ impl<T> BoundedSpsc<T> {
     /// ```
     ///
     /// This ensures that `next_head` wraps back to zero once it reaches the capacity.
+    ///
+    /// # Safety
+    ///
+    /// Only one thread at a time may act as the producer: `push` must never
+    /// run concurrently with another `push` on the same queue.
     #[inline(always)]
-    pub(crate) fn push(&self, value: T) -> Result<(), T> {
+    pub(crate) unsafe fn push(&self, value: T) -> Result<(), T> {
         let curr_head = self.next_head.load(Relaxed);
 
         // Wraparound logic:
@@ -191,8 +283,14 @@ impl<T> BoundedSpsc<T> {
     /// ```
     ///
     /// This logic wraps the tail index to zero once it reaches capacity.
+    ///
+    /// # Safety
+    ///
+    /// Only one thread at a time may act as the consumer: `pop` must never
+    /// run concurrently with another `pop` or [`peek`](Self::peek) on the
+    /// same queue.
     #[inline(always)]
-    pub(crate) fn pop(&self) -> Option<T> {
+    pub(crate) unsafe fn pop(&self) -> Option<T> {
         let curr_tail = self.tail.load(Relaxed);
 
         if self.next_head.load(Acquire) == curr_tail {
@@ -211,6 +309,21 @@ impl<T> BoundedSpsc<T> {
         Some(value)
     }
 
+    /// Returns a reference to the oldest value without removing it.
+    ///
+    /// # Safety
+    ///
+    /// The same single-consumer rule as for [`pop`](Self::pop) applies, and
+    /// the consumer must not pop while the returned reference is alive.
+    #[inline(always)]
+    pub(crate) unsafe fn peek(&self) -> Option<&T> {
+        let curr_tail = self.tail.load(Relaxed);
+        if self.next_head.load(Acquire) == curr_tail {
+            return None; // Queue is empty
+        }
+        Some(unsafe { self.buffer.get_ref(curr_tail) })
+    }
+
     /// Returns `true` if the queue is empty.
     #[inline(always)]
     pub(crate) fn is_empty(&self) -> bool {
@@ -229,30 +342,46 @@ impl<T> BoundedSpsc<T> {
         next_head == self.tail.load(Acquire)
     }
 
-    /// Returns the capacity of the queue.
+    /// Returns how many values the queue can hold: one less than its
+    /// slots, since one slot always stays empty.
     #[inline(always)]
     pub(crate) fn capacity(&self) -> usize {
-        self.buffer.capacity
+        self.buffer.capacity - 1
+    }
+
+    /// Returns the number of values currently in the queue.
+    ///
+    /// # Wrap-around logic
+    ///
+    /// When `next_head` has wrapped around behind `tail`, the occupied slots
+    /// are `tail..capacity` followed by `0..next_head`.
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        let head = self.next_head.load(Acquire);
+        let tail = self.tail.load(Acquire);
+        if head >= tail {
+            head - tail
+        } else {
+            self.buffer.capacity - tail + head
+        }
     }
 }
 
-impl<T> Drop for BoundedSpsc<T> {
+impl<T, A: SpscAlloc> Drop for BoundedSpsc<T, A> {
+    /// Drops the values still in the ring, oldest first.
+    ///
+    /// If one of them panics, the rest are still dropped; the buffer itself
+    /// is freed by `Array`'s own `Drop` either way.
     fn drop(&mut self) {
-        unsafe {
-            let head = self.next_head.load(Acquire);
-            let tail = self.tail.load(Acquire);
-            let mut idx = tail;
-            // Iterate from tail to head, dropping all initialized elements
-            while idx != head {
-                let ptr = self.buffer.buffer.as_ptr().add(idx);
-                (*ptr).assume_init_drop();
-                idx += 1;
-                // Wrap-around logic on drop iterator as well
-                idx *= (idx < self.buffer.capacity) as usize;
-            }
-        }
+        let tail = self.tail.load(Acquire);
+        let capacity = self.buffer.capacity;
+        // Wrap-around logic on the drop iterator as well
+        let occupied = (0..self.len()).map(|offset| (tail + offset) % capacity);
+        for_each_unwinding(occupied, |idx| unsafe { self.buffer.drop_in_place(idx) });
     }
 }
 
-unsafe impl<T> Send for Array<T> {}
-unsafe impl<T> Sync for Array<T> {}
+// Values move from the producer's thread to the consumer's, so both halves
+// sharing the buffer is only sound for `T: Send`.
+unsafe impl<T: Send, A: SpscAlloc> Send for Array<T, A> {}
+unsafe impl<T: Send, A: SpscAlloc> Sync for Array<T, A> {}
@@ -1,7 +1,9 @@
 mod channel;
 pub(crate) mod inner_spsc;
 
-pub use channel::BoundedSpscChannel;
+pub use channel::{
+    BoundedSpscChannel, Channel, Drain, IntoIter, Iter, Receiver, Sender, TryIter,
+};
 
 #[cfg(test)]
 mod tests {
@@ -18,6 +20,7 @@ mod tests {
     #[test]
     fn full_and_empty() {
         let (sender, receiver) = BoundedSpscChannel::split(2);
+        assert_eq!(sender.capacity(), 2);
         assert!(sender.send(42).is_ok());
         assert!(sender.send(42).is_ok());
         assert!(sender.send(99).is_err()); // Should be full
@@ -47,4 +50,100 @@ mod tests {
         t.join().unwrap();
         assert_eq!(sum, (0..100_000u64).sum());
     }
+
+    #[test]
+    fn try_iter_drains_until_empty() {
+        let (sender, receiver) = BoundedSpscChannel::split(4);
+        (&sender).extend([1, 2, 3]);
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(!receiver.is_disconnected());
+        drop(sender);
+        assert!(receiver.is_disconnected());
+        assert_eq!(receiver.try_iter().next(), None);
+    }
+
+    #[test]
+    fn iter_ends_on_disconnect() {
+        let (sender, receiver) = BoundedSpscChannel::split(8);
+        let t = thread::spawn(move || {
+            (&sender).extend(0..10_000u64); // blocks whenever the buffer is full
+        });
+        assert_eq!(receiver.into_iter().sum::<u64>(), (0..10_000u64).sum());
+        t.join().unwrap();
+    }
+
+    #[test]
+    fn send_blocking_fails_without_receiver() {
+        let (sender, receiver) = BoundedSpscChannel::split(1);
+        assert_eq!(sender.send_blocking(1), Ok(()));
+        let t = thread::spawn(move || {
+            assert_eq!(receiver.recv_blocking(), Some(1));
+            assert_eq!(receiver.recv_blocking(), Some(2));
+        });
+        assert_eq!(sender.send_blocking(2), Ok(())); // waits for the first recv
+        t.join().unwrap();
+        assert!(sender.is_disconnected());
+        assert_eq!(sender.send_blocking(3), Err(3));
+    }
+
+    // Zero-sized values never touch the heap; these also run under Miri in CI.
+    #[test]
+    fn zst_signals() {
+        let (sender, receiver) = BoundedSpscChannel::split::<()>(3);
+        assert!((0..3).all(|_| sender.send(()).is_ok()));
+        assert_eq!(sender.send(()), Err(()));
+        assert_eq!(receiver.len(), 3);
+        assert_eq!(receiver.recv(), Some(()));
+        assert!(sender.send(()).is_ok());
+        assert_eq!(receiver.try_iter().count(), 3);
+        assert_eq!(receiver.recv(), None);
+    }
+
+    #[test]
+    fn zst_values_dropped_once() {
+        use std::cell::Cell;
+
+        thread_local!(static DROPS: Cell<usize> = const { Cell::new(0) });
+        struct Token;
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPS.set(DROPS.get() + 1);
+            }
+        }
+
+        let (sender, receiver) = BoundedSpscChannel::split(4);
+        for _ in 0..4 {
+            assert!(sender.send(Token).is_ok());
+        }
+        drop(sender.send(Token).unwrap_err());
+        drop(receiver.recv());
+        assert_eq!(DROPS.get(), 2);
+        drop((sender, receiver));
+        assert_eq!(DROPS.get(), 5);
+    }
+
+    #[test]
+    fn reunite_recovers_undelivered_values() {
+        let (sender, receiver) = BoundedSpscChannel::split(4);
+        (&sender).extend(0..4);
+        assert_eq!(receiver.recv(), Some(0));
+        sender.send(4).unwrap(); // wraps around the ring
+
+        let mut channel = sender.reunite(receiver).unwrap();
+        assert_eq!((channel.len(), channel.capacity()), (4, 4));
+        assert_eq!(channel.drain().next(), Some(1));
+        assert_eq!(channel.into_vec(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn reunite_refuses_foreign_receiver() {
+        let (sender, _receiver) = BoundedSpscChannel::split(1);
+        let (_other, foreign) = BoundedSpscChannel::split(1);
+        let err = sender.reunite(foreign).unwrap_err();
+        assert_eq!(err.to_string(), "tried to reunite halves of different channels");
+        let (sender, foreign) = (err.0, err.1);
+        // Both halves come back still connected.
+        sender.send(1).unwrap();
+        assert!(!sender.is_disconnected() && !foreign.is_disconnected());
+    }
 }
@@ -0,0 +1,351 @@
+//! One place to configure a channel before creating it.
+//!
+//! The `split_*` constructors of each channel cover one setting at a time.
+//! A [`ChannelBuilder`] gathers them all, the wait strategy, what a send does
+//! when the channel is full, the allocator, the observer, whether metrics are
+//! counted and a name for debugging, and then builds either a bounded or an
+//! unbounded channel. Building never panics: every problem comes back as a
+//! [`ChannelError`].
+//!
+//! # Example
+//! ```
+//! use lock_free_spsc::spsc::builder::{ChannelBuilder, Overflow, WaitStrategy};
+//!
+//! let (tx, rx) = ChannelBuilder::<u64>::new()
+//!     .name("orders")
+//!     .wait_strategy(WaitStrategy::SpinThenPark(100))
+//!     .overflow(Overflow::Block)
+//!     .build_bounded(3)
+//!     .unwrap();
+//! assert_eq!(tx.capacity(), 3);
+//! assert_eq!(rx.name(), Some("orders"));
+//!
+//! let consumer = std::thread::spawn(move || rx.iter().sum::<u64>());
+//! // The channel blocks instead of refusing values once it holds three.
+//! (0..100).for_each(|i| tx.send(i).unwrap());
+//! drop(tx);
+//! assert_eq!(consumer.join().unwrap(), (0..100).sum());
+//! ```
+
+use crate::spsc::alloc::{Global, SpscAlloc};
+use crate::spsc::bounded_spsc::{self, BoundedSpscChannel, inner_spsc::BoundedSpsc};
+use crate::spsc::error::ChannelError;
+use crate::spsc::observer::{NoopObserver, SpscObserver};
+use crate::spsc::unbounded_spsc::raw_spsc::RawSpsc;
+use crate::spsc::unbounded_spsc::{
+    self, DEFAULT_SPARE_SEGMENTS, Limit, SegmentSize, UnboundSpscChannel,
+};
+use std::fmt;
+
+/// How a blocking operation waits for the other side of the channel.
+///
+/// Used by `send_blocking`, `recv_blocking`, the blocking iterators, and by
+/// `send` when the channel [blocks on overflow](Overflow::Block).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitStrategy {
+    /// Park the thread until the other side wakes it.
+    #[default]
+    Park,
+    /// Poll in a busy loop and never park: the lowest latency, at the cost
+    /// of a core per waiting thread.
+    Spin,
+    /// Poll, yielding to the scheduler between polls, and never park.
+    Yield,
+    /// Poll in a busy loop this many times, then park.
+    SpinThenPark(u32),
+}
+
+/// What `send` does when the channel has no room for a value.
+///
+/// A bounded channel has no room once it holds its capacity; an unbounded one
+/// once it reaches its [`Limit`], and never without one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Hand the value back.
+    #[default]
+    Reject,
+    /// Wait, as the [`WaitStrategy`] says, until the receiver makes room.
+    /// The value is still handed back if the receiver is dropped.
+    Block,
+}
+
+/// The settings a channel keeps from the builder that created it.
+pub(crate) struct Options {
+    pub(crate) wait: WaitStrategy,
+    pub(crate) overflow: Overflow,
+    pub(crate) metrics: bool,
+    pub(crate) name: Option<Box<str>>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            wait: WaitStrategy::Park,
+            overflow: Overflow::Reject,
+            metrics: true,
+            name: None,
+        }
+    }
+}
+
+/// The two halves of a bounded channel, as built by [`ChannelBuilder`].
+pub type BoundedHalves<T, O, A> =
+    (bounded_spsc::Sender<T, O, A>, bounded_spsc::Receiver<T, O, A>);
+
+/// The two halves of an unbounded channel, as built by [`ChannelBuilder`].
+pub type UnboundedHalves<T, O, A> =
+    (unbounded_spsc::Sender<T, O, A>, unbounded_spsc::Receiver<T, O, A>);
+
+/// Configures a channel, then builds it bounded or unbounded.
+///
+/// Every setting has a default matching the plain `split` constructors:
+/// parking waits, rejecting sends, the global allocator, no observer,
+/// metrics counted when the `metrics` feature is enabled, and no name.
+pub struct ChannelBuilder<T, O = NoopObserver, A = Global> {
+    options: Options,
+    observer: O,
+    alloc: A,
+    limit: Limit<T>,
+    segment_size: SegmentSize,
+    spare_segments: usize,
+}
+
+impl<T> ChannelBuilder<T> {
+    /// Creates a builder with every setting at its default.
+    pub fn new() -> Self {
+        ChannelBuilder {
+            options: Options::default(),
+            observer: NoopObserver,
+            alloc: Global,
+            limit: Limit::default(),
+            segment_size: SegmentSize::default(),
+            spare_segments: DEFAULT_SPARE_SEGMENTS,
+        }
+    }
+}
+
+impl<T> Default for ChannelBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> ChannelBuilder<T, O, A> {
+    /// Sets how blocking operations wait.
+    pub fn wait_strategy(mut self, wait: WaitStrategy) -> Self {
+        self.options.wait = wait;
+        self
+    }
+
+    /// Sets what `send` does when the channel has no room.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.options.overflow = overflow;
+        self
+    }
+
+    /// Turns the channel's counters on or off.
+    ///
+    /// Counting is on by default; a channel built with `false` reports
+    /// zeroed counters from `stats`. Without the `metrics` feature nothing
+    /// is counted either way.
+    pub fn metrics(mut self, enabled: bool) -> Self {
+        self.options.metrics = enabled;
+        self
+    }
+
+    /// Names the channel. Both halves report the name through `name` and
+    /// show it in their `Debug` output.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.options.name = Some(name.into().into_boxed_str());
+        self
+    }
+
+    /// Reports the channel's events to `observer`.
+    ///
+    /// See [`observer`](crate::spsc::observer) for the available hooks.
+    pub fn observer<P: SpscObserver>(self, observer: P) -> ChannelBuilder<T, P, A> {
+        ChannelBuilder {
+            options: self.options,
+            observer,
+            alloc: self.alloc,
+            limit: self.limit,
+            segment_size: self.segment_size,
+            spare_segments: self.spare_segments,
+        }
+    }
+
+    /// Allocates the channel's storage from `alloc`.
+    ///
+    /// See [`alloc`](crate::spsc::alloc) for the allocator interface.
+    pub fn alloc<B: SpscAlloc>(self, alloc: B) -> ChannelBuilder<T, O, B> {
+        ChannelBuilder {
+            options: self.options,
+            observer: self.observer,
+            alloc,
+            limit: self.limit,
+            segment_size: self.segment_size,
+            spare_segments: self.spare_segments,
+        }
+    }
+
+    /// Caps how much an unbounded channel may hold. Ignored when building a
+    /// bounded channel, whose capacity is its limit.
+    ///
+    /// With [`Overflow::Block`] the limit is made [`blocking`](Limit::blocking).
+    pub fn limit(mut self, limit: Limit<T>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Sizes an unbounded channel's segments. Ignored when building a bounded
+    /// channel.
+    pub fn segment_size(mut self, size: SegmentSize) -> Self {
+        self.segment_size = size;
+        self
+    }
+
+    /// Sets how many drained segments an unbounded channel keeps for reuse.
+    /// Ignored when building a bounded channel.
+    pub fn spare_segments(mut self, max_spare: usize) -> Self {
+        self.spare_segments = max_spare;
+        self
+    }
+
+    /// Builds a bounded channel that holds up to `capacity` values.
+    ///
+    /// Both halves report exactly `capacity` from `capacity()`. Fails if
+    /// `capacity` is zero or the ring cannot be allocated.
+    pub fn build_bounded(self, capacity: usize) -> Result<BoundedHalves<T, O, A>, ChannelError> {
+        let queue = BoundedSpsc::try_new_in(capacity, self.alloc)?;
+        Ok(BoundedSpscChannel::from_queue(queue, self.observer, self.options))
+    }
+
+    /// Builds an unbounded channel, capped by the [`limit`](Self::limit) if
+    /// one was set.
+    ///
+    /// Fails if the first segment cannot be allocated.
+    pub fn build_unbounded(self) -> Result<UnboundedHalves<T, O, A>, ChannelError> {
+        let (size, spares) = (self.segment_size, self.spare_segments);
+        let queue = RawSpsc::try_with_options_in(size, spares, self.alloc)?;
+        let limit = match self.options.overflow {
+            Overflow::Block => self.limit.blocking(),
+            Overflow::Reject => self.limit,
+        };
+        Ok(UnboundSpscChannel::from_queue(queue, limit, None, self.observer, self.options))
+    }
+}
+
+impl<T, O, A> fmt::Debug for ChannelBuilder<T, O, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelBuilder")
+            .field("name", &self.options.name)
+            .field("wait_strategy", &self.options.wait)
+            .field("overflow", &self.options.overflow)
+            .field("metrics", &self.options.metrics)
+            .field("limit", &self.limit)
+            .field("segment_size", &self.segment_size)
+            .field("spare_segments", &self.spare_segments)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelBuilder, Overflow, WaitStrategy};
+    use crate::spsc::alloc::RecordingAlloc;
+    use crate::spsc::error::ChannelError;
+    use crate::spsc::unbounded_spsc::{Limit, SegmentSize};
+    use std::thread;
+
+    #[test]
+    fn bounded_capacity_is_exact() {
+        let (tx, rx) = ChannelBuilder::new().build_bounded(3).unwrap();
+        assert_eq!((tx.capacity(), rx.capacity()), (3, 3));
+        assert!((0..3).all(|i| tx.send(i).is_ok()));
+        assert_eq!(tx.send(3), Err(3));
+        assert!(rx.is_full());
+
+        let zero = ChannelBuilder::<u8>::new().build_bounded(0);
+        assert_eq!(zero.err(), Some(ChannelError::ZeroCapacity));
+    }
+
+    #[test]
+    fn name_shows_in_debug() {
+        let (tx, rx) = ChannelBuilder::<u8>::new().name("orders").build_bounded(2).unwrap();
+        assert_eq!(tx.name(), Some("orders"));
+        assert!(format!("{rx:?}").contains("\"orders\""));
+
+        let (tx, rx) = ChannelBuilder::<u8>::new().build_unbounded().unwrap();
+        assert_eq!(rx.name(), None);
+        assert!(format!("{tx:?}").starts_with("Sender { name: None"));
+    }
+
+    #[test]
+    fn bounded_send_blocks_on_overflow() {
+        // Pure spinning hands over only when the scheduler preempts, which
+        // is too slow to test on a single core.
+        for wait in [WaitStrategy::Park, WaitStrategy::Yield, WaitStrategy::SpinThenPark(16)] {
+            let (tx, rx) = ChannelBuilder::new()
+                .wait_strategy(wait)
+                .overflow(Overflow::Block)
+                .build_bounded(2)
+                .unwrap();
+            let consumer = thread::spawn(move || rx.iter().sum::<u64>());
+            (0..1000).for_each(|i| tx.send(i).unwrap());
+            drop(tx);
+            assert_eq!(consumer.join().unwrap(), (0..1000).sum());
+        }
+    }
+
+    #[test]
+    fn spinning_receiver_sees_disconnect() {
+        let (tx, rx) = ChannelBuilder::new()
+            .wait_strategy(WaitStrategy::Spin)
+            .build_unbounded()
+            .unwrap();
+        (&tx).extend(0..3);
+        drop(tx);
+        assert!(rx.iter().eq(0..3));
+    }
+
+    #[test]
+    fn unbounded_limit_blocks_on_overflow() {
+        let (tx, rx) = ChannelBuilder::new()
+            .wait_strategy(WaitStrategy::SpinThenPark(64))
+            .overflow(Overflow::Block)
+            .limit(Limit::segments(1))
+            .segment_size(SegmentSize::slots(4))
+            .build_unbounded()
+            .unwrap();
+        let consumer = thread::spawn(move || rx.iter().sum::<u64>());
+        (0..1000).for_each(|i| tx.send(i).unwrap());
+        assert!(tx.usage().segments <= 1);
+        drop(tx);
+        assert_eq!(consumer.join().unwrap(), (0..1000).sum());
+    }
+
+    #[test]
+    fn storage_comes_from_the_allocator() {
+        let alloc = RecordingAlloc::new();
+        let (tx, rx) = ChannelBuilder::new()
+            .alloc(&alloc)
+            .spare_segments(0)
+            .build_unbounded()
+            .unwrap();
+        (&tx).extend(0..1000);
+        assert!(rx.try_iter().eq(0..1000));
+        assert_eq!(alloc.live_bytes(), rx.usage().bytes);
+        drop((tx, rx));
+        assert_eq!(alloc.live_bytes(), 0);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_can_be_turned_off() {
+        let (tx, rx) = ChannelBuilder::new().metrics(false).build_bounded(2).unwrap();
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(rx.recv(), None);
+        assert_eq!(rx.stats(), Default::default());
+    }
+}
@@ -0,0 +1,429 @@
+//! Differential testing of the queues against a [`VecDeque`] model.
+//!
+//! A [`Program`] is a capacity plus a list of [`Op`]s. [`Program::run`] plays
+//! it against every queue flavour and against a `VecDeque` bounded to the same
+//! capacity, asserting after each step that both gave the same answer. Every
+//! value is tagged with an id and counts its own drops, so a value that is
+//! lost, duplicated or dropped twice fails the run as well.
+//!
+//! The fan-in and priority channels only promise FIFO order within one lane
+//! or level, so they run with a single one. Neither can peek or report its
+//! length, so [`Op::Peek`] and [`Op::Len`] are skipped for them.
+//!
+//! Programs are decoded from raw bytes, so the same harness serves the
+//! randomized unit tests below and the `differential` target in `fuzz/`.
+//! This module is an internal testing aid and is only compiled for tests and
+//! with the `fuzzing` feature.
+
+use crate::spsc::bounded_spsc::{self, BoundedSpscChannel, inner_spsc::BoundedSpsc};
+use crate::spsc::fan_in::{self, SpscFanIn};
+use crate::spsc::priority::{self, PriorityChannel};
+use crate::spsc::unbounded_spsc::{self, LimitExceeded, UnboundSpscChannel, raw_spsc::RawSpsc};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// One step of a [`Program`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Sends one value.
+    Send,
+    /// Receives one value.
+    Recv,
+    /// Sends this many values in a row.
+    SendBatch(u8),
+    /// Receives this many times in a row.
+    RecvBatch(u8),
+    /// Looks at the next value without receiving it.
+    Peek,
+    /// Compares the number of queued values.
+    Len,
+    /// Drops the queue with whatever it holds and starts over with a new one.
+    /// Channels drop their sender first when the flag is set.
+    DropHandle(bool),
+}
+
+impl Op {
+    /// Decodes one operation from the front of `bytes`, or returns `None`
+    /// once they run out.
+    pub fn decode(bytes: &mut &[u8]) -> Option<Op> {
+        let (&tag, rest) = bytes.split_first()?;
+        *bytes = rest;
+        let mut arg = || {
+            let (&arg, rest) = bytes.split_first().unwrap_or((&0, &[]));
+            *bytes = rest;
+            arg
+        };
+        Some(match tag % 7 {
+            0 => Op::Send,
+            1 => Op::Recv,
+            2 => Op::SendBatch(arg()),
+            3 => Op::RecvBatch(arg()),
+            4 => Op::Peek,
+            5 => Op::Len,
+            _ => Op::DropHandle(arg() & 1 == 1),
+        })
+    }
+}
+
+/// A sequence of operations to run against every queue flavour.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    /// Usable capacity of the bounded flavours, between 1 and 16.
+    pub capacity: usize,
+    /// The operations, in order.
+    pub ops: Vec<Op>,
+}
+
+impl Program {
+    /// Decodes a program: the first byte picks the capacity and the rest are
+    /// operations. Any byte string is a valid program.
+    pub fn decode(mut bytes: &[u8]) -> Program {
+        let capacity = bytes.first().map_or(1, |&b| 1 + (b % 16) as usize);
+        bytes = bytes.get(1..).unwrap_or_default();
+        let ops = std::iter::from_fn(|| Op::decode(&mut bytes)).collect();
+        Program { capacity, ops }
+    }
+
+    /// Runs the program against every flavour, panicking on the first
+    /// divergence from the model or on a lost or doubly dropped value.
+    pub fn run(&self) {
+        self.run_on::<BoundedSpsc<Tracked>>();
+        self.run_on::<RawSpsc<Tracked>>();
+        self.run_on::<BoundedHalves>();
+        self.run_on::<UnboundedHalves>();
+        self.run_on::<FanInLane>();
+        self.run_on::<PriorityLevel>();
+    }
+
+    fn run_on<S: Subject>(&self) {
+        let tracker = Tracker::default();
+        let bound = S::BOUNDED.then_some(self.capacity);
+        let mut subject = S::new(self.capacity);
+        let mut model = VecDeque::new();
+
+        let send = |subject: &mut S, model: &mut VecDeque<usize>| {
+            let value = tracker.create();
+            let id = value.id;
+            let expected = if bound.is_some_and(|bound| model.len() == bound) {
+                Err(id)
+            } else {
+                model.push_back(id);
+                Ok(())
+            };
+            // A rejected value comes back to us and is dropped here.
+            assert_eq!(subject.send(value).map_err(|v| v.id), expected, "send");
+        };
+        let recv = |subject: &mut S, model: &mut VecDeque<usize>| {
+            assert_eq!(subject.recv().map(|v| v.id), model.pop_front(), "recv");
+        };
+
+        for op in &self.ops {
+            match *op {
+                Op::Send => send(&mut subject, &mut model),
+                Op::Recv => recv(&mut subject, &mut model),
+                Op::SendBatch(n) => (0..n).for_each(|_| send(&mut subject, &mut model)),
+                Op::RecvBatch(n) => (0..n).for_each(|_| recv(&mut subject, &mut model)),
+                Op::Peek if S::INSPECTABLE => {
+                    assert_eq!(subject.peek(), model.front().copied(), "peek")
+                }
+                Op::Len if S::INSPECTABLE => assert_eq!(subject.len(), model.len(), "len"),
+                Op::Peek | Op::Len => {}
+                Op::DropHandle(sender_first) => {
+                    std::mem::replace(&mut subject, S::new(self.capacity)).close(sender_first);
+                    model.clear();
+                }
+            }
+        }
+        drop(subject);
+        tracker.assert_all_dropped_once();
+    }
+}
+
+/// Per-id drop counts shared by every value of one run.
+#[derive(Default)]
+struct Tracker {
+    drops: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Tracker {
+    fn create(&self) -> Tracked {
+        let mut drops = self.drops.borrow_mut();
+        drops.push(0);
+        Tracked {
+            id: drops.len() - 1,
+            drops: Rc::clone(&self.drops),
+        }
+    }
+
+    fn assert_all_dropped_once(&self) {
+        let drops = self.drops.borrow();
+        if let Some(id) = drops.iter().position(|&count| count != 1) {
+            panic!("value {id} was dropped {} times", drops[id]);
+        }
+    }
+}
+
+/// A value that records its own drop.
+struct Tracked {
+    id: usize,
+    drops: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        let count = &mut self.drops.borrow_mut()[self.id];
+        *count += 1;
+        assert_eq!(*count, 1, "value {} dropped twice", self.id);
+    }
+}
+
+/// A queue flavour under test, driven from a single thread.
+trait Subject {
+    /// Whether sends fail once the capacity is reached.
+    const BOUNDED: bool;
+    /// Whether `peek` and `len` are available.
+    const INSPECTABLE: bool = true;
+
+    fn new(capacity: usize) -> Self;
+    fn send(&mut self, value: Tracked) -> Result<(), Tracked>;
+    fn recv(&mut self) -> Option<Tracked>;
+
+    fn peek(&mut self) -> Option<usize> {
+        unreachable!("peek is only called when INSPECTABLE")
+    }
+    fn len(&self) -> usize {
+        unreachable!("len is only called when INSPECTABLE")
+    }
+
+    /// Drops the queue; flavours with two halves honour the order.
+    fn close(self, sender_first: bool)
+    where
+        Self: Sized,
+    {
+        let _ = sender_first;
+    }
+}
+
+impl Subject for BoundedSpsc<Tracked> {
+    const BOUNDED: bool = true;
+
+    fn new(capacity: usize) -> Self {
+        BoundedSpsc::new(capacity)
+    }
+    // Subjects are driven from a single thread, which is both sides at once.
+    fn send(&mut self, value: Tracked) -> Result<(), Tracked> {
+        unsafe { self.push(value) }
+    }
+    fn recv(&mut self) -> Option<Tracked> {
+        unsafe { self.pop() }
+    }
+    fn peek(&mut self) -> Option<usize> {
+        unsafe { BoundedSpsc::peek(self) }.map(|v| v.id)
+    }
+    fn len(&self) -> usize {
+        BoundedSpsc::len(self)
+    }
+}
+
+impl Subject for RawSpsc<Tracked> {
+    const BOUNDED: bool = false;
+
+    fn new(_: usize) -> Self {
+        RawSpsc::new()
+    }
+    fn send(&mut self, value: Tracked) -> Result<(), Tracked> {
+        unsafe { self.push(value) };
+        Ok(())
+    }
+    fn recv(&mut self) -> Option<Tracked> {
+        unsafe { self.pop() }
+    }
+    fn peek(&mut self) -> Option<usize> {
+        unsafe { RawSpsc::peek(self) }.map(|v| v.id)
+    }
+    fn len(&self) -> usize {
+        unsafe { RawSpsc::len(self) }
+    }
+}
+
+struct BoundedHalves(
+    bounded_spsc::Sender<Tracked>,
+    bounded_spsc::Receiver<Tracked>,
+);
+
+impl Subject for BoundedHalves {
+    const BOUNDED: bool = true;
+
+    fn new(capacity: usize) -> Self {
+        let (sender, receiver) = BoundedSpscChannel::split(capacity);
+        BoundedHalves(sender, receiver)
+    }
+    fn send(&mut self, value: Tracked) -> Result<(), Tracked> {
+        self.0.send(value)
+    }
+    fn recv(&mut self) -> Option<Tracked> {
+        self.1.recv()
+    }
+    fn peek(&mut self) -> Option<usize> {
+        self.1.peek().map(|v| v.id)
+    }
+    fn len(&self) -> usize {
+        assert_eq!(self.0.len(), self.1.len());
+        self.1.len()
+    }
+    fn close(self, sender_first: bool) {
+        let BoundedHalves(sender, receiver) = self;
+        if sender_first {
+            drop(sender);
+        } else {
+            drop(receiver);
+        }
+    }
+}
+
+struct UnboundedHalves(
+    unbounded_spsc::Sender<Tracked>,
+    unbounded_spsc::Receiver<Tracked>,
+);
+
+impl Subject for UnboundedHalves {
+    const BOUNDED: bool = false;
+
+    fn new(_: usize) -> Self {
+        let (sender, receiver) = UnboundSpscChannel::split();
+        UnboundedHalves(sender, receiver)
+    }
+    fn send(&mut self, value: Tracked) -> Result<(), Tracked> {
+        self.0.send(value).map_err(LimitExceeded::into_inner)
+    }
+    fn recv(&mut self) -> Option<Tracked> {
+        self.1.recv()
+    }
+    fn peek(&mut self) -> Option<usize> {
+        self.1.peek().map(|v| v.id)
+    }
+    fn len(&self) -> usize {
+        let len = self.1.len();
+        assert_eq!(self.1.is_empty(), len == 0);
+        len
+    }
+    fn close(self, sender_first: bool) {
+        let UnboundedHalves(sender, receiver) = self;
+        if sender_first {
+            drop(sender);
+        } else {
+            drop(receiver);
+        }
+    }
+}
+
+/// A fan-in channel with a single lane.
+struct FanInLane(fan_in::Sender<Tracked>, fan_in::Receiver<Tracked>);
+
+impl Subject for FanInLane {
+    const BOUNDED: bool = true;
+    const INSPECTABLE: bool = false;
+
+    fn new(capacity: usize) -> Self {
+        let (fan_in, receiver) = SpscFanIn::split(capacity);
+        FanInLane(fan_in.new_sender(), receiver)
+    }
+    fn send(&mut self, value: Tracked) -> Result<(), Tracked> {
+        self.0.send(value)
+    }
+    fn recv(&mut self) -> Option<Tracked> {
+        self.1.recv()
+    }
+    fn close(self, sender_first: bool) {
+        let FanInLane(sender, receiver) = self;
+        if sender_first {
+            drop(sender);
+        } else {
+            drop(receiver);
+        }
+    }
+}
+
+/// A priority channel with a single level.
+struct PriorityLevel(priority::Sender<Tracked>, priority::Receiver<Tracked>);
+
+impl Subject for PriorityLevel {
+    const BOUNDED: bool = true;
+    const INSPECTABLE: bool = false;
+
+    fn new(capacity: usize) -> Self {
+        let (sender, receiver) = PriorityChannel::split(1, capacity);
+        PriorityLevel(sender, receiver)
+    }
+    fn send(&mut self, value: Tracked) -> Result<(), Tracked> {
+        self.0.send(0, value)
+    }
+    fn recv(&mut self) -> Option<Tracked> {
+        self.1.recv()
+    }
+    fn close(self, sender_first: bool) {
+        let PriorityLevel(sender, receiver) = self;
+        if sender_first {
+            drop(sender);
+        } else {
+            drop(receiver);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Op, Program};
+
+    /// xorshift64, enough to spread programs over the op space.
+    fn random_bytes(mut state: u64, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode() {
+        let program = Program::decode(&[3, 0, 2, 200, 10, 4, 6, 1, 5]);
+        assert_eq!(program.capacity, 4);
+        assert_eq!(
+            program.ops,
+            [
+                Op::Send,
+                Op::SendBatch(200),
+                Op::RecvBatch(4),
+                Op::DropHandle(true),
+                Op::Len,
+            ]
+        );
+        assert_eq!(Program::decode(&[]).ops, []);
+    }
+
+    #[test]
+    fn crosses_segments() {
+        // Fill several unbounded segments, peek across the boundaries, then
+        // drop the queue while it still holds values.
+        let mut bytes = vec![0];
+        for _ in 0..4 {
+            bytes.extend([2, 255, 5, 4]);
+        }
+        for _ in 0..3 {
+            bytes.extend([3, 200, 4, 5]);
+        }
+        bytes.extend([6, 0, 2, 10, 6, 1]);
+        Program::decode(&bytes).run();
+    }
+
+    #[test]
+    fn random_programs() {
+        for seed in 1..=300 {
+            Program::decode(&random_bytes(seed, 256)).run();
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Helpers that keep the queues' `Drop` impls panic-safe.
+//!
+//! A queue being dropped runs `T::drop` for every value it still holds, and
+//! any of those calls may panic. The helpers here make sure the remaining
+//! values are still dropped and the buffers still freed while the panic
+//! unwinds. A second panic during that unwinding aborts the process, as it
+//! would for any other drop glue; nothing is ever dropped or freed twice.
+
+use std::mem::ManuallyDrop;
+
+/// Calls `f` on every item of `iter`.
+///
+/// If one call panics, the remaining items are still passed to `f` while the
+/// panic unwinds, and the panic then carries on.
+pub(crate) fn for_each_unwinding<I: Iterator, F: FnMut(I::Item)>(iter: I, f: F) {
+    struct Rest<I: Iterator, F: FnMut(I::Item)> {
+        iter: I,
+        f: F,
+    }
+
+    impl<I: Iterator, F: FnMut(I::Item)> Drop for Rest<I, F> {
+        fn drop(&mut self) {
+            // Only reached with items left when a call below panicked.
+            for item in &mut self.iter {
+                (self.f)(item);
+            }
+        }
+    }
+
+    let mut rest = Rest { iter, f };
+    for item in rest.iter.by_ref() {
+        (rest.f)(item);
+    }
+}
+
+/// Runs a closure when dropped, including while unwinding.
+pub(crate) struct OnDrop<F: FnOnce()>(ManuallyDrop<F>);
+
+impl<F: FnOnce()> OnDrop<F> {
+    pub(crate) fn new(f: F) -> Self {
+        Self(ManuallyDrop::new(f))
+    }
+}
+
+impl<F: FnOnce()> Drop for OnDrop<F> {
+    fn drop(&mut self) {
+        // Taken exactly once, here.
+        let f = unsafe { ManuallyDrop::take(&mut self.0) };
+        f();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spsc::bounded_spsc::{BoundedSpscChannel, inner_spsc::BoundedSpsc};
+    use crate::spsc::unbounded_spsc::{UnboundSpscChannel, raw_spsc::RawSpsc};
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+    /// Hands out values that count their drops, and optionally panic in `drop`.
+    struct Drops(Arc<[AtomicUsize]>);
+
+    impl Drops {
+        fn new(count: usize) -> Self {
+            Drops((0..count).map(|_| AtomicUsize::new(0)).collect())
+        }
+
+        fn value(&self, id: usize) -> Counted {
+            Counted {
+                id,
+                drops: Arc::clone(&self.0),
+                panics: false,
+            }
+        }
+
+        fn panicking(&self, id: usize) -> Counted {
+            Counted {
+                id,
+                drops: Arc::clone(&self.0),
+                panics: true,
+            }
+        }
+
+        fn assert_each_once(&self) {
+            for (id, count) in self.0.iter().enumerate() {
+                assert_eq!(count.load(Relaxed), 1, "value {id}");
+            }
+        }
+    }
+
+    struct Counted {
+        id: usize,
+        drops: Arc<[AtomicUsize]>,
+        panics: bool,
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.drops[self.id].fetch_add(1, Relaxed);
+            if self.panics {
+                panic!("value {} panicked in drop", self.id);
+            }
+        }
+    }
+
+    // The raw queues are driven from the test thread alone.
+    #[test]
+    fn bounded_popped_rejected_and_remaining() {
+        let drops = Drops::new(6);
+        let queue = BoundedSpsc::new(4);
+        for id in 0..4 {
+            assert!(unsafe { queue.push(drops.value(id)) }.is_ok());
+        }
+        drop(unsafe { queue.push(drops.value(4)) }.unwrap_err());
+        drop(unsafe { queue.pop() });
+        drop(unsafe { queue.pop() });
+        // Wrap around so the values left behind straddle the end of the ring.
+        assert!(unsafe { queue.push(drops.value(5)) }.is_ok());
+        drop(queue);
+        drops.assert_each_once();
+    }
+
+    #[test]
+    fn bounded_drop_survives_panicking_value() {
+        let drops = Drops::new(5);
+        let queue = BoundedSpsc::new(4);
+        assert!(unsafe { queue.push(drops.value(0)) }.is_ok());
+        drop(unsafe { queue.pop() });
+        for id in 1..5 {
+            let value = if id == 2 {
+                drops.panicking(id)
+            } else {
+                drops.value(id)
+            };
+            assert!(unsafe { queue.push(value) }.is_ok());
+        }
+        assert!(catch_unwind(AssertUnwindSafe(|| drop(queue))).is_err());
+        drops.assert_each_once();
+    }
+
+    #[test]
+    fn raw_drop_survives_panicking_value() {
+        // Spread the values over several segments, with the panic in the first.
+        let drops = Drops::new(400);
+        let queue = RawSpsc::new();
+        for id in 0..400 {
+            let value = if id == 3 {
+                drops.panicking(id)
+            } else {
+                drops.value(id)
+            };
+            unsafe { queue.push(value) };
+        }
+        drop(unsafe { queue.pop() });
+        assert!(catch_unwind(AssertUnwindSafe(|| drop(queue))).is_err());
+        drops.assert_each_once();
+    }
+
+    #[test]
+    fn channels_drop_each_value_once() {
+        let drops = Drops::new(8);
+        let (sender, receiver) = BoundedSpscChannel::split(2);
+        assert!(sender.send(drops.value(0)).is_ok());
+        assert!(sender.send(drops.value(1)).is_ok());
+        drop(sender.send(drops.value(2)).unwrap_err());
+        drop(receiver.recv());
+        drop(receiver);
+        // A value sent after the receiver is gone is still dropped with the channel.
+        assert!(sender.send(drops.value(3)).is_ok());
+        drop(sender);
+
+        let (sender, receiver) = UnboundSpscChannel::split();
+        (4..8).for_each(|id| sender.send(drops.value(id)).unwrap());
+        drop(receiver.recv());
+        drop((sender, receiver));
+        drops.assert_each_once();
+    }
+
+    #[test]
+    fn extend_with_panicking_iterator() {
+        // Values sent before the iterator panics stay queued and are dropped once.
+        let drops = Drops::new(3);
+        let (sender, receiver) = BoundedSpscChannel::split(4);
+        let values = (0..3).map(|id| {
+            if id == 2 {
+                panic!("iterator failed");
+            }
+            drops.value(id)
+        });
+        assert!(catch_unwind(AssertUnwindSafe(|| (&sender).extend(values))).is_err());
+        drop(drops.value(2));
+        assert_eq!(receiver.len(), 2);
+        drop((sender, receiver));
+        drops.assert_each_once();
+    }
+}
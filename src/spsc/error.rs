@@ -0,0 +1,195 @@
+//! Errors reported by the fallible channel constructors and sends, and by
+//! reuniting a channel's halves.
+//!
+//! The plain constructors, such as
+//! [`BoundedSpscChannel::split`](crate::spsc::bounded_spsc::BoundedSpscChannel::split),
+//! panic when a channel cannot be created. Their `try_` counterparts return a
+//! [`ChannelError`] instead, so a program can fall back to a smaller buffer or
+//! another allocator:
+//!
+//! ```
+//! use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+//! use lock_free_spsc::spsc::error::ChannelError;
+//!
+//! let zero = BoundedSpscChannel::try_split::<u8>(0).err();
+//! assert_eq!(zero, Some(ChannelError::ZeroCapacity));
+//! let huge = BoundedSpscChannel::try_split::<u64>(usize::MAX / 4).err();
+//! assert_eq!(huge, Some(ChannelError::LayoutOverflow));
+//! ```
+
+use super::alloc::AllocError;
+use std::error::Error;
+use std::fmt;
+
+/// Why a channel could not be created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelError {
+    /// A bounded channel was asked for a capacity of zero.
+    ZeroCapacity,
+    /// The buffer would be larger than `isize::MAX` bytes.
+    LayoutOverflow,
+    /// The allocator could not provide the buffer.
+    Alloc,
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChannelError::ZeroCapacity => "channel capacity must be greater than zero",
+            ChannelError::LayoutOverflow => "channel buffer size overflows isize::MAX",
+            ChannelError::Alloc => "channel buffer allocation failed",
+        })
+    }
+}
+
+impl Error for ChannelError {}
+
+impl From<AllocError> for ChannelError {
+    fn from(AllocError: AllocError) -> Self {
+        ChannelError::Alloc
+    }
+}
+
+/// The error returned by the unbounded channel's
+/// [`Sender::try_send`](crate::spsc::unbounded_spsc::Sender::try_send),
+/// holding the value that was not sent.
+#[derive(PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel's [`Limit`](crate::spsc::unbounded_spsc::Limit) was reached.
+    Full(T),
+    /// The value needed a new segment, and allocating it failed.
+    Alloc(T, ChannelError),
+}
+
+impl<T> TrySendError<T> {
+    /// Returns the value that was not sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Alloc(value, _) => value,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Alloc(_, err) => write!(f, "Alloc(.., {err:?})"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("sending on a channel that reached its limit"),
+            TrySendError::Alloc(_, err) => err.fmt(f),
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+/// The error returned by `Sender::reunite` when the receiver belongs to
+/// another channel, holding both halves unchanged.
+pub struct ReuniteError<S, R>(pub S, pub R);
+
+impl<S, R> fmt::Debug for ReuniteError<S, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ReuniteError(..)")
+    }
+}
+
+impl<S, R> fmt::Display for ReuniteError<S, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("tried to reunite halves of different channels")
+    }
+}
+
+impl<S, R> Error for ReuniteError<S, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelError, TrySendError};
+    use crate::spsc::alloc::{AllocError, RecordingAlloc, SpscAlloc};
+    use crate::spsc::bounded_spsc::BoundedSpscChannel;
+    use crate::spsc::unbounded_spsc::UnboundSpscChannel;
+    use std::alloc::Layout;
+    use std::ptr::NonNull;
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+    /// Grants a fixed number of allocations, then fails every further one.
+    struct Budget {
+        left: AtomicUsize,
+        inner: RecordingAlloc,
+    }
+
+    impl Budget {
+        fn new(allocations: usize) -> Self {
+            Budget { left: AtomicUsize::new(allocations), inner: RecordingAlloc::new() }
+        }
+    }
+
+    unsafe impl SpscAlloc for Budget {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+            let grant = |left: usize| left.checked_sub(1);
+            self.left.fetch_update(Relaxed, Relaxed, grant).map_err(|_| AllocError)?;
+            self.inner.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            unsafe { self.inner.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn bounded_rejects_what_it_cannot_build() {
+        let zero = BoundedSpscChannel::try_split::<u8>(0);
+        assert_eq!(zero.err(), Some(ChannelError::ZeroCapacity));
+        let huge = BoundedSpscChannel::try_split::<u8>(usize::MAX);
+        assert_eq!(huge.err(), Some(ChannelError::LayoutOverflow));
+
+        let alloc = Budget::new(0);
+        let failed = BoundedSpscChannel::try_split_in::<u64, _>(4, &alloc);
+        assert_eq!(failed.err(), Some(ChannelError::Alloc));
+        assert!(alloc.inner.events().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "channel capacity must be greater than zero")]
+    fn bounded_split_panics_on_zero_capacity() {
+        let _ = BoundedSpscChannel::split::<u8>(0);
+    }
+
+    #[test]
+    fn unbounded_frees_half_built_segment() {
+        // The buffer is allocated, the header is not.
+        let alloc = Budget::new(1);
+        let failed = UnboundSpscChannel::try_split_in::<u64, _>(&alloc);
+        assert_eq!(failed.err(), Some(ChannelError::Alloc));
+        assert_eq!(alloc.inner.allocations(), 1);
+        assert_eq!(alloc.inner.live_bytes(), 0);
+    }
+
+    #[test]
+    fn try_send_hands_back_what_it_cannot_allocate() {
+        // Enough for the first segment only.
+        let alloc = Budget::new(2);
+        let (tx, rx) = UnboundSpscChannel::try_split_in(&alloc).unwrap();
+        let mut sent = 0;
+        let rejected = loop {
+            match tx.try_send(sent) {
+                Ok(()) => sent += 1,
+                Err(err) => break err,
+            }
+        };
+        assert!(matches!(rejected, TrySendError::Alloc(_, ChannelError::Alloc)));
+        assert_eq!(rejected.into_inner(), sent);
+        assert_eq!(rx.usage().segments, 1);
+
+        // Draining the segment makes room in it again.
+        assert!(rx.try_iter().eq(0..sent));
+        tx.try_send(sent).unwrap();
+        assert_eq!(rx.recv(), Some(sent));
+    }
+}
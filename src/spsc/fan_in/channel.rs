@@ -0,0 +1,336 @@
+//! Multi-producer, single-consumer channel built from independent SPSC lanes.
+//!
+//! Every [`Sender`] owns a private [`BoundedSpsc`] lane, so producers never
+//! contend with each other on the hot path: a send is exactly one bounded SPSC
+//! push. The single [`Receiver`] polls all lanes, either round-robin or by
+//! priority, and may park when every lane is empty.
+//!
+//! # Example
+//! ```
+//! use lock_free_spsc::spsc::fan_in::SpscFanIn;
+//! use std::thread;
+//!
+//! let (fan_in, mut rx) = SpscFanIn::split(64);
+//!
+//! let producers: Vec<_> = (0..4u64)
+//!     .map(|id| {
+//!         let tx = fan_in.new_sender();
+//!         thread::spawn(move || {
+//!             while tx.send(id).is_err() {}
+//!         })
+//!     })
+//!     .collect();
+//! drop(fan_in);
+//!
+//! let mut sum = 0;
+//! while let Some(id) = rx.recv_blocking() {
+//!     sum += id;
+//! }
+//! assert_eq!(sum, 0 + 1 + 2 + 3);
+//! producers.into_iter().for_each(|p| p.join().unwrap());
+//! ```
+//!
+//! # Lane lifecycle
+//! [`SpscFanIn::new_sender`] allocates a lane and hands it to the receiver
+//! through a mutex-protected registration list; the receiver only takes that
+//! lock when a flag says new lanes are pending. When a [`Sender`] is dropped
+//! its lane is marked closed, and the receiver discards the lane as soon as
+//! it has drained the remaining items.
+
+use crate::spsc::bounded_spsc::inner_spsc::BoundedSpsc;
+use crate::spsc::error::ChannelError;
+use crate::spsc::waiter::{WaitSlot, wait};
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::marker::PhantomData;
+use std::sync::atomic::{
+    AtomicBool, AtomicUsize,
+    Ordering::{AcqRel, Acquire, Relaxed, Release},
+};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The order in which a [`Receiver`] polls its lanes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FanInPolicy {
+    /// Resume polling after the lane that produced the previous item, so that
+    /// a busy producer cannot starve the others.
+    #[default]
+    RoundRobin,
+    /// Always poll lanes from the highest to the lowest priority, so a lane
+    /// is only read once every higher-priority lane is empty. Lanes with equal
+    /// priority are polled in registration order.
+    Priority,
+}
+
+struct Lane<T> {
+    queue: BoundedSpsc<T>,
+    priority: u8,
+    closed: AtomicBool,
+}
+
+struct Shared<T> {
+    lane_capacity: usize,
+    pending: Mutex<Vec<Arc<Lane<T>>>>,
+    has_pending: AtomicBool,
+    registrars: AtomicUsize,
+    rx_slot: WaitSlot,
+}
+
+/// Entry point of a fan-in channel, used to register new producers.
+///
+/// The handle can be cloned and shared freely: registration is the only
+/// operation that takes a lock. Once every `SpscFanIn` handle and every
+/// [`Sender`] has been dropped, the [`Receiver`] reports disconnection.
+pub struct SpscFanIn<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The sending half of one lane of a fan-in channel.
+///
+/// Each sender writes to its own bounded lane, so it fails with the original
+/// value when that lane is full, independently of the other producers.
+pub struct Sender<T> {
+    lane: Arc<Lane<T>>,
+    shared: Arc<Shared<T>>,
+    /// `!Sync`: each lane allows a single producer thread.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// The single receiving half of a fan-in channel.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    lanes: Vec<Arc<Lane<T>>>,
+    cursor: usize,
+    policy: FanInPolicy,
+}
+
+impl<T> SpscFanIn<T> {
+    /// Creates a fan-in channel whose lanes each hold `lane_capacity` items.
+    ///
+    /// Returns the registration handle and the single [`Receiver`]. No lane
+    /// exists until [`new_sender`](Self::new_sender) is called.
+    ///
+    /// # Panics
+    /// Panics if `lane_capacity` is zero; use [`try_split`](Self::try_split)
+    /// to handle that case.
+    pub fn split(lane_capacity: usize) -> (SpscFanIn<T>, Receiver<T>) {
+        Self::try_split(lane_capacity).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`split`](Self::split), but returns a [`ChannelError`] instead of
+    /// panicking.
+    ///
+    /// # Example
+    /// ```
+    /// use lock_free_spsc::spsc::error::ChannelError;
+    /// use lock_free_spsc::spsc::fan_in::SpscFanIn;
+    ///
+    /// let err = SpscFanIn::<u8>::try_split(0).err();
+    /// assert_eq!(err, Some(ChannelError::ZeroCapacity));
+    /// ```
+    pub fn try_split(lane_capacity: usize) -> Result<(SpscFanIn<T>, Receiver<T>), ChannelError> {
+        if lane_capacity == 0 {
+            return Err(ChannelError::ZeroCapacity);
+        }
+        let shared = Arc::new(Shared {
+            lane_capacity,
+            pending: Mutex::new(Vec::new()),
+            has_pending: AtomicBool::new(false),
+            registrars: AtomicUsize::new(1),
+            rx_slot: WaitSlot::new(),
+        });
+        let receiver = Receiver {
+            shared: Arc::clone(&shared),
+            lanes: Vec::new(),
+            cursor: 0,
+            policy: FanInPolicy::default(),
+        };
+        Ok((SpscFanIn { shared }, receiver))
+    }
+
+    /// Registers a fresh lane with the default priority `0` and returns its sender.
+    pub fn new_sender(&self) -> Sender<T> {
+        self.new_sender_with_priority(0)
+    }
+
+    /// Registers a fresh lane with the given priority and returns its sender.
+    ///
+    /// The priority is only taken into account under [`FanInPolicy::Priority`];
+    /// higher values are polled first.
+    pub fn new_sender_with_priority(&self, priority: u8) -> Sender<T> {
+        let lane = Arc::new(Lane {
+            queue: BoundedSpsc::new(self.shared.lane_capacity),
+            priority,
+            closed: AtomicBool::new(false),
+        });
+        let mut pending = self
+            .shared
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        pending.push(Arc::clone(&lane));
+        self.shared.has_pending.store(true, Release);
+        drop(pending);
+        Sender {
+            lane,
+            shared: Arc::clone(&self.shared),
+            _not_sync: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for SpscFanIn<T> {
+    fn clone(&self) -> Self {
+        self.shared.registrars.fetch_add(1, Relaxed);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for SpscFanIn<T> {
+    fn drop(&mut self) {
+        if self.shared.registrars.fetch_sub(1, AcqRel) == 1 {
+            self.shared.rx_slot.notify();
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Attempts to send a value into this sender's lane.
+    ///
+    /// Returns `Err(value)` if the lane is full.
+    #[inline(always)]
+    pub fn send(&self, value: T) -> Result<(), T> {
+        // Each lane has exactly one sender, which is neither `Clone` nor `Sync`.
+        unsafe { self.lane.queue.push(value) }?;
+        self.shared.rx_slot.notify();
+        Ok(())
+    }
+
+    /// Returns `true` if this sender's lane is currently full.
+    #[inline(always)]
+    pub fn is_full(&self) -> bool {
+        self.lane.queue.is_full()
+    }
+
+    /// Returns the priority this sender's lane was registered with.
+    pub fn priority(&self) -> u8 {
+        self.lane.priority
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.lane.closed.store(true, Release);
+        self.shared.rx_slot.notify();
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Sets the order in which lanes are polled.
+    pub fn set_policy(&mut self, policy: FanInPolicy) {
+        self.policy = policy;
+        self.sort_lanes();
+    }
+
+    /// Returns the current polling policy.
+    pub fn policy(&self) -> FanInPolicy {
+        self.policy
+    }
+
+    /// Returns the number of lanes the receiver is still polling.
+    ///
+    /// A lane stops being counted once its sender has been dropped and its
+    /// remaining items have been received.
+    pub fn lanes(&mut self) -> usize {
+        self.adopt_pending();
+        self.lanes.len()
+    }
+
+    /// Attempts to receive a value from any lane.
+    ///
+    /// Returns `None` if every lane is currently empty.
+    pub fn recv(&mut self) -> Option<T> {
+        self.adopt_pending();
+        let mut start = match self.policy {
+            FanInPolicy::RoundRobin if !self.lanes.is_empty() => self.cursor % self.lanes.len(),
+            _ => 0,
+        };
+        // Poll `start..`, then wrap around to `..start`.
+        let (mut idx, mut wrapped) = (start, false);
+        loop {
+            if !wrapped && idx == self.lanes.len() {
+                (idx, wrapped) = (0, true);
+            }
+            if wrapped && idx == start {
+                return None;
+            }
+            let lane = &self.lanes[idx];
+            // The sender publishes everything before marking its lane closed,
+            // so a lane that was closed before it came up empty is finished.
+            let closed = lane.closed.load(Acquire);
+            // The receiver is the only consumer of every lane.
+            if let Some(value) = unsafe { lane.queue.pop() } {
+                self.cursor = idx + 1;
+                return Some(value);
+            }
+            if !closed {
+                idx += 1;
+                continue;
+            }
+            // Shifting keeps the priority order; the next lane moves into
+            // `idx`, and so does `start` if it lies past the removed lane.
+            drop(self.lanes.remove(idx));
+            if wrapped {
+                start -= 1;
+            }
+        }
+    }
+
+    /// Receives a value, parking the thread while every lane is empty.
+    ///
+    /// Returns `None` once every [`Sender`] and every [`SpscFanIn`] handle has
+    /// been dropped and all lanes are drained.
+    pub fn recv_blocking(&mut self) -> Option<T> {
+        let shared = Arc::clone(&self.shared);
+        wait(&[&shared.rx_slot], |_| {}, || match self.recv() {
+            Some(value) => Some(Some(value)),
+            None if self.is_disconnected() => Some(None),
+            None => None,
+        })
+    }
+
+    /// Returns `true` if no lane can ever produce another item.
+    pub fn is_disconnected(&mut self) -> bool {
+        if self.shared.registrars.load(Acquire) != 0 {
+            return false;
+        }
+        self.adopt_pending();
+        self.lanes
+            .iter()
+            .all(|lane| lane.closed.load(Acquire) && lane.queue.is_empty())
+    }
+
+    fn adopt_pending(&mut self) {
+        if !self.shared.has_pending.load(Acquire) {
+            return;
+        }
+        let mut pending = self
+            .shared
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.shared.has_pending.store(false, Relaxed);
+        self.lanes.append(&mut pending);
+        drop(pending);
+        self.sort_lanes();
+    }
+
+    fn sort_lanes(&mut self) {
+        if self.policy == FanInPolicy::Priority {
+            // Stable, so equal priorities keep their registration order.
+            self.lanes.sort_by_key(|lane| Reverse(lane.priority));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+mod channel;
+
+pub use channel::{FanInPolicy, Receiver, Sender, SpscFanIn};
+
+#[cfg(test)]
+mod tests {
+    use super::{FanInPolicy, SpscFanIn};
+    use crate::spsc::error::ChannelError;
+    use std::thread;
+
+    #[test]
+    fn round_robin_alternates_lanes() {
+        let (fan_in, mut receiver) = SpscFanIn::split(8);
+        let a = fan_in.new_sender();
+        let b = fan_in.new_sender();
+        for i in 0..3 {
+            a.send(('a', i)).unwrap();
+            b.send(('b', i)).unwrap();
+        }
+        let order: Vec<_> = (0..6).map(|_| receiver.recv().unwrap().0).collect();
+        assert_eq!(order, ['a', 'b', 'a', 'b', 'a', 'b']);
+        assert_eq!(receiver.recv(), None);
+    }
+
+    #[test]
+    fn priority_drains_higher_lanes_first() {
+        let (fan_in, mut receiver) = SpscFanIn::split(8);
+        receiver.set_policy(FanInPolicy::Priority);
+        let low = fan_in.new_sender_with_priority(1);
+        let high = fan_in.new_sender_with_priority(9);
+        low.send(1).unwrap();
+        low.send(2).unwrap();
+        high.send(10).unwrap();
+        assert_eq!(receiver.recv(), Some(10));
+        high.send(11).unwrap();
+        assert_eq!(receiver.recv(), Some(11));
+        assert_eq!(receiver.recv(), Some(1));
+        assert_eq!(receiver.recv(), Some(2));
+    }
+
+    #[test]
+    fn lane_removed_after_sender_drop() {
+        let (fan_in, mut receiver) = SpscFanIn::split(4);
+        let a = fan_in.new_sender();
+        let b = fan_in.new_sender();
+        a.send(1).unwrap();
+        drop(a);
+        assert_eq!(receiver.lanes(), 2);
+        assert_eq!(receiver.recv(), Some(1)); // Remaining items are still delivered
+        assert_eq!(receiver.recv(), None);
+        assert_eq!(receiver.lanes(), 1);
+        drop(b);
+        drop(fan_in);
+        assert_eq!(receiver.recv_blocking(), None);
+        assert_eq!(receiver.lanes(), 0);
+    }
+
+    #[test]
+    fn closed_lanes_pruned_while_others_are_busy() {
+        let (fan_in, mut receiver) = SpscFanIn::split(4);
+        let a = fan_in.new_sender();
+        let b = fan_in.new_sender();
+        let c = fan_in.new_sender();
+        let d = fan_in.new_sender();
+        a.send(1).unwrap();
+        a.send(2).unwrap();
+        c.send(3).unwrap();
+        drop((b, d));
+        assert_eq!(receiver.recv(), Some(1));
+        assert_eq!(receiver.recv(), Some(3)); // b is pruned on the way
+        assert_eq!(receiver.recv(), Some(2)); // and so is d
+        assert_eq!(receiver.lanes(), 2);
+    }
+
+    #[test]
+    fn pruning_below_the_cursor_after_wrapping() {
+        let (fan_in, mut receiver) = SpscFanIn::split(4);
+        let a = fan_in.new_sender();
+        let b = fan_in.new_sender();
+        let c = fan_in.new_sender();
+        let _d = fan_in.new_sender();
+        c.send(1).unwrap();
+        assert_eq!(receiver.recv(), Some(1)); // The next scan starts at d
+        c.send(2).unwrap();
+        drop((a, b));
+        assert_eq!(receiver.recv(), Some(2)); // Wraps, pruning a and b before c
+        assert_eq!(receiver.lanes(), 2);
+        assert_eq!(receiver.recv(), None);
+    }
+
+    #[test]
+    fn pruning_keeps_priority_order() {
+        let (fan_in, mut receiver) = SpscFanIn::split(4);
+        receiver.set_policy(FanInPolicy::Priority);
+        let high = fan_in.new_sender_with_priority(3);
+        drop(fan_in.new_sender_with_priority(2));
+        let low = fan_in.new_sender_with_priority(1);
+        low.send(10).unwrap();
+        assert_eq!(receiver.recv(), Some(10));
+        assert_eq!(receiver.lanes(), 2);
+        low.send(11).unwrap();
+        high.send(1).unwrap();
+        assert_eq!(receiver.recv(), Some(1));
+        assert_eq!(receiver.recv(), Some(11));
+    }
+
+    #[test]
+    fn zero_lane_capacity_rejected() {
+        let zero = SpscFanIn::<u8>::try_split(0);
+        assert_eq!(zero.err(), Some(ChannelError::ZeroCapacity));
+    }
+
+    #[test]
+    fn multithreaded_fan_in() {
+        const PER_PRODUCER: u64 = 10_000;
+        let (fan_in, mut receiver) = SpscFanIn::split(128);
+        let producers: Vec<_> = (0..4)
+            .map(|_| {
+                let sender = fan_in.new_sender();
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        while sender.send(i).is_err() {} // retry if full
+                    }
+                })
+            })
+            .collect();
+        drop(fan_in);
+
+        let mut sum = 0;
+        while let Some(v) = receiver.recv_blocking() {
+            sum += v;
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(sum, 4 * (0..PER_PRODUCER).sum::<u64>());
+    }
+}
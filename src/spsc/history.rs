@@ -0,0 +1,388 @@
+//! Threaded stress tests that check recorded histories for linearizability.
+//!
+//! A producer and a consumer thread hammer a queue while every operation is
+//! recorded with the ticks of a shared logical clock taken just before it is
+//! invoked and just after it returns. Once both threads are done, the
+//! histories are checked against a sequential FIFO queue.
+//!
+//! With a single producer and a single consumer each side's operations are
+//! totally ordered, and the producer sends `0, 1, 2, ...` in order, so the
+//! check needs no search. A history is accepted when:
+//!
+//! - the values received are exactly `0, 1, 2, ...` in order, with nothing
+//!   lost, duplicated or reordered;
+//! - no receive of a value returned before that value's send was invoked;
+//! - no receive came back empty although the next value to be received had
+//!   been sent before it was invoked;
+//! - no send was rejected as full unless the queue could have held
+//!   `capacity` values at some point while it ran.
+//!
+//! The last two rules are what a segment handoff that drops or skips values
+//! breaks, even when the values that do arrive are in order.
+
+use crate::spsc::bounded_spsc::{BoundedSpscChannel, inner_spsc::BoundedSpsc};
+use crate::spsc::fan_in::SpscFanIn;
+use crate::spsc::priority::PriorityChannel;
+use crate::spsc::unbounded_spsc::{LimitExceeded, UnboundSpscChannel, raw_spsc::RawSpsc};
+use crate::spsc::{bounded_spsc, fan_in, priority, unbounded_spsc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
+use std::thread;
+
+/// A logical clock shared by both threads.
+#[derive(Default)]
+struct Clock(AtomicU64);
+
+impl Clock {
+    fn tick(&self) -> u64 {
+        self.0.fetch_add(1, SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SendEvent {
+    value: u64,
+    invoke: u64,
+    respond: u64,
+    accepted: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RecvEvent {
+    invoke: u64,
+    respond: u64,
+    value: Option<u64>,
+}
+
+/// Checks a history against a sequential FIFO queue holding at most
+/// `capacity` values, or an unbounded one for `None`.
+fn check(capacity: Option<usize>, sends: &[SendEvent], recvs: &[RecvEvent]) -> Result<(), String> {
+    let accepted: Vec<_> = sends.iter().filter(|send| send.accepted).collect();
+    if let Some((i, send)) = accepted
+        .iter()
+        .enumerate()
+        .find(|(i, send)| send.value != *i as u64)
+    {
+        return Err(format!("the producer sent {} as value #{i}", send.value));
+    }
+
+    let mut received: Vec<&RecvEvent> = Vec::new();
+    for recv in recvs {
+        let next = received.len();
+        match recv.value {
+            Some(value) => {
+                if value != next as u64 {
+                    return Err(format!("received {value} while {next} was next"));
+                }
+                let Some(send) = accepted.get(next) else {
+                    return Err(format!("received {value}, which was never sent"));
+                };
+                if recv.respond < send.invoke {
+                    return Err(format!("received {value} before it was sent"));
+                }
+                received.push(recv);
+            }
+            None => {
+                if let Some(send) = accepted.get(next)
+                    && send.respond < recv.invoke
+                {
+                    return Err(format!(
+                        "reported empty at {} while {next} was queued",
+                        recv.invoke
+                    ));
+                }
+            }
+        }
+    }
+
+    let Some(capacity) = capacity else {
+        if sends.len() != accepted.len() {
+            return Err("an unbounded queue rejected a value".to_string());
+        }
+        return Ok(());
+    };
+    let mut sent = 0usize;
+    for send in sends {
+        if send.accepted {
+            sent += 1;
+            continue;
+        }
+        // Full means the oldest of the last `capacity` values was still queued.
+        let Some(oldest) = sent.checked_sub(capacity) else {
+            return Err(format!(
+                "reported full at {} holding only {sent} values",
+                send.invoke
+            ));
+        };
+        if let Some(recv) = received.get(oldest)
+            && recv.respond < send.invoke
+        {
+            return Err(format!(
+                "reported full at {} after {oldest} was received",
+                send.invoke
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A queue type under test, split into a producer and a consumer.
+trait Flavour {
+    /// Whether sends fail once the capacity is reached.
+    const BOUNDED: bool;
+    type Producer: Send + 'static;
+    type Consumer: Send + 'static;
+
+    fn split(capacity: usize) -> (Self::Producer, Self::Consumer);
+    fn send(producer: &mut Self::Producer, value: u64) -> Result<(), u64>;
+    fn recv(consumer: &mut Self::Consumer) -> Option<u64>;
+}
+
+/// Sends `count` values through a fresh queue on two threads and checks the
+/// recorded history.
+fn stress<F: Flavour>(capacity: usize, count: u64) {
+    let clock = Arc::new(Clock::default());
+    let (mut producer, mut consumer) = F::split(capacity);
+
+    let producer = thread::spawn({
+        let clock = Arc::clone(&clock);
+        move || {
+            let mut history = Vec::new();
+            let mut value = 0;
+            while value < count {
+                let invoke = clock.tick();
+                let accepted = F::send(&mut producer, value).is_ok();
+                let respond = clock.tick();
+                history.push(SendEvent {
+                    value,
+                    invoke,
+                    respond,
+                    accepted,
+                });
+                if accepted {
+                    value += 1;
+                } else {
+                    thread::yield_now();
+                }
+            }
+            history
+        }
+    });
+
+    let mut recvs = Vec::new();
+    loop {
+        // Checked before the receive, so an empty result afterwards means
+        // every send had returned and a lost value shows up as a stale empty.
+        let finished = producer.is_finished();
+        let invoke = clock.tick();
+        let value = F::recv(&mut consumer);
+        let respond = clock.tick();
+        recvs.push(RecvEvent {
+            invoke,
+            respond,
+            value,
+        });
+        if value.is_none() {
+            if finished {
+                break;
+            }
+            thread::yield_now();
+        }
+    }
+    let sends = producer.join().unwrap();
+
+    let capacity = F::BOUNDED.then_some(capacity);
+    if let Err(violation) = check(capacity, &sends, &recvs) {
+        panic!("{}: {violation}", std::any::type_name::<F>());
+    }
+}
+
+impl Flavour for BoundedSpsc<u64> {
+    const BOUNDED: bool = true;
+    type Producer = Arc<BoundedSpsc<u64>>;
+    type Consumer = Arc<BoundedSpsc<u64>>;
+
+    fn split(capacity: usize) -> (Self::Producer, Self::Consumer) {
+        let queue = Arc::new(BoundedSpsc::new(capacity));
+        (Arc::clone(&queue), queue)
+    }
+    // `stress` pushes on one thread only and pops on another only.
+    fn send(producer: &mut Self::Producer, value: u64) -> Result<(), u64> {
+        unsafe { producer.push(value) }
+    }
+    fn recv(consumer: &mut Self::Consumer) -> Option<u64> {
+        unsafe { consumer.pop() }
+    }
+}
+
+impl Flavour for RawSpsc<u64> {
+    const BOUNDED: bool = false;
+    type Producer = Arc<RawSpsc<u64>>;
+    type Consumer = Arc<RawSpsc<u64>>;
+
+    fn split(_: usize) -> (Self::Producer, Self::Consumer) {
+        let queue = Arc::new(RawSpsc::new());
+        (Arc::clone(&queue), queue)
+    }
+    fn send(producer: &mut Self::Producer, value: u64) -> Result<(), u64> {
+        unsafe { producer.push(value) };
+        Ok(())
+    }
+    fn recv(consumer: &mut Self::Consumer) -> Option<u64> {
+        unsafe { consumer.pop() }
+    }
+}
+
+impl Flavour for BoundedSpscChannel {
+    const BOUNDED: bool = true;
+    type Producer = bounded_spsc::Sender<u64>;
+    type Consumer = bounded_spsc::Receiver<u64>;
+
+    fn split(capacity: usize) -> (Self::Producer, Self::Consumer) {
+        BoundedSpscChannel::split(capacity)
+    }
+    fn send(producer: &mut Self::Producer, value: u64) -> Result<(), u64> {
+        producer.send(value)
+    }
+    fn recv(consumer: &mut Self::Consumer) -> Option<u64> {
+        consumer.recv()
+    }
+}
+
+impl Flavour for UnboundSpscChannel {
+    const BOUNDED: bool = false;
+    type Producer = unbounded_spsc::Sender<u64>;
+    type Consumer = unbounded_spsc::Receiver<u64>;
+
+    fn split(_: usize) -> (Self::Producer, Self::Consumer) {
+        UnboundSpscChannel::split()
+    }
+    fn send(producer: &mut Self::Producer, value: u64) -> Result<(), u64> {
+        producer.send(value).map_err(LimitExceeded::into_inner)
+    }
+    fn recv(consumer: &mut Self::Consumer) -> Option<u64> {
+        consumer.recv()
+    }
+}
+
+/// A fan-in channel with a single registered producer.
+impl Flavour for SpscFanIn<u64> {
+    const BOUNDED: bool = true;
+    type Producer = fan_in::Sender<u64>;
+    type Consumer = fan_in::Receiver<u64>;
+
+    fn split(capacity: usize) -> (Self::Producer, Self::Consumer) {
+        let (fan_in, receiver) = SpscFanIn::split(capacity);
+        (fan_in.new_sender(), receiver)
+    }
+    fn send(producer: &mut Self::Producer, value: u64) -> Result<(), u64> {
+        producer.send(value)
+    }
+    fn recv(consumer: &mut Self::Consumer) -> Option<u64> {
+        consumer.recv()
+    }
+}
+
+/// A priority channel with a single level.
+impl Flavour for PriorityChannel {
+    const BOUNDED: bool = true;
+    type Producer = priority::Sender<u64>;
+    type Consumer = priority::Receiver<u64>;
+
+    fn split(capacity: usize) -> (Self::Producer, Self::Consumer) {
+        PriorityChannel::split(1, capacity)
+    }
+    fn send(producer: &mut Self::Producer, value: u64) -> Result<(), u64> {
+        producer.send(0, value)
+    }
+    fn recv(consumer: &mut Self::Consumer) -> Option<u64> {
+        consumer.recv()
+    }
+}
+
+const COUNT: u64 = 20_000;
+
+#[test]
+fn bounded_spsc() {
+    stress::<BoundedSpsc<u64>>(4, COUNT);
+}
+
+#[test]
+fn raw_spsc() {
+    stress::<RawSpsc<u64>>(0, COUNT);
+}
+
+#[test]
+fn bounded_channel() {
+    stress::<BoundedSpscChannel>(4, COUNT);
+}
+
+#[test]
+fn unbounded_channel() {
+    stress::<UnboundSpscChannel>(0, COUNT);
+}
+
+#[test]
+fn fan_in_single_lane() {
+    stress::<SpscFanIn<u64>>(4, COUNT);
+}
+
+#[test]
+fn priority_single_level() {
+    stress::<PriorityChannel>(4, COUNT);
+}
+
+fn send(value: u64, invoke: u64, respond: u64, accepted: bool) -> SendEvent {
+    SendEvent {
+        value,
+        invoke,
+        respond,
+        accepted,
+    }
+}
+
+fn recv(invoke: u64, respond: u64, value: Option<u64>) -> RecvEvent {
+    RecvEvent {
+        invoke,
+        respond,
+        value,
+    }
+}
+
+#[test]
+fn checker_accepts_overlapping_operations() {
+    // The receive of 0 overlaps its send, and the empty receive overlaps the
+    // send of 1, so both can be ordered before the respective send completed.
+    let sends = [send(0, 0, 3, true), send(1, 4, 7, true)];
+    let recvs = [recv(1, 2, Some(0)), recv(5, 6, None), recv(8, 9, Some(1))];
+    assert_eq!(check(None, &sends, &recvs), Ok(()));
+}
+
+#[test]
+fn checker_rejects_lost_and_reordered_values() {
+    let sends = [send(0, 0, 1, true), send(1, 2, 3, true)];
+    let skipped = [recv(4, 5, Some(1))];
+    assert!(check(None, &sends, &skipped).is_err());
+    let duplicated = [recv(4, 5, Some(0)), recv(6, 7, Some(0))];
+    assert!(check(None, &sends, &duplicated).is_err());
+}
+
+#[test]
+fn checker_rejects_stale_empty() {
+    // Value 0 was sent long before the receive, which still saw nothing:
+    // what a consumer skipping over a sealed segment would report.
+    let sends = [send(0, 0, 1, true)];
+    let recvs = [recv(2, 3, None), recv(4, 5, Some(0))];
+    assert!(check(None, &sends, &recvs).unwrap_err().contains("empty"));
+}
+
+#[test]
+fn checker_rejects_spurious_full() {
+    let sends = [send(0, 0, 1, true), send(1, 2, 3, false)];
+    assert!(check(Some(2), &sends, &[]).is_err());
+    assert_eq!(check(Some(1), &sends, &[]), Ok(()));
+    // Once 0 has been received, a queue of capacity 1 cannot be full.
+    let recvs = [recv(4, 5, Some(0))];
+    let late_full = [send(0, 0, 1, true), send(1, 6, 7, false)];
+    assert!(check(Some(1), &late_full, &recvs).is_err());
+}
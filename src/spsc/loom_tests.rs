@@ -0,0 +1,221 @@
+//! Model-checked tests for the lock-free queues.
+//!
+//! These only exist when the crate is built with `--cfg loom`, which swaps
+//! the queues' atomics and slot cells for loom's instrumented versions (see
+//! `crate::sync`). Loom then runs each test body under every interleaving
+//! the memory model allows. Run them in release mode with their own target
+//! directory so the regular build cache is left alone:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" CARGO_TARGET_DIR=target/loom cargo test --release --lib loom_tests
+//! ```
+//!
+//! `LOOM_MAX_PREEMPTIONS=2` bounds the search further if a test takes too long.
+
+use crate::spsc::bounded_spsc::inner_spsc::BoundedSpsc;
+use crate::spsc::unbounded_spsc::raw_spsc::RawSpsc;
+use loom::sync::Arc;
+use loom::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use loom::thread;
+
+// Each test pushes from one thread only and pops from another only, as the
+// queues' `unsafe` entry points require.
+
+/// Counts how many times it was dropped.
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Relaxed);
+    }
+}
+
+#[test]
+fn bounded_push_pop() {
+    loom::model(|| {
+        let queue = Arc::new(BoundedSpsc::new(2));
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 0..2 {
+                    unsafe { queue.push(i) }.unwrap();
+                }
+            })
+        };
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            match unsafe { queue.pop() } {
+                Some(value) => received.push(value),
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+        assert_eq!(received, [0, 1]);
+        assert!(unsafe { queue.pop() }.is_none());
+    });
+}
+
+#[test]
+fn bounded_wrap_around() {
+    loom::model(|| {
+        // One usable slot forces every push to reuse the slot just popped.
+        let queue = Arc::new(BoundedSpsc::new(1));
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 0..3 {
+                    while unsafe { queue.push(i) }.is_err() {
+                        thread::yield_now();
+                    }
+                }
+            })
+        };
+
+        for expected in 0..3 {
+            loop {
+                if let Some(value) = unsafe { queue.pop() } {
+                    assert_eq!(value, expected);
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+        producer.join().unwrap();
+    });
+}
+
+#[test]
+fn bounded_drop_with_pending_items() {
+    loom::model(|| {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let queue = Arc::new(BoundedSpsc::new(2));
+        let producer = {
+            let (queue, drops) = (Arc::clone(&queue), Arc::clone(&drops));
+            thread::spawn(move || {
+                for _ in 0..2 {
+                    let _ = unsafe { queue.push(DropCounter(Arc::clone(&drops))) };
+                }
+            })
+        };
+
+        drop(unsafe { queue.pop() });
+        producer.join().unwrap();
+        drop(queue);
+        assert_eq!(drops.load(Relaxed), 2);
+    });
+}
+
+#[test]
+fn unbounded_segment_handoff() {
+    loom::model(|| {
+        // Under loom a segment holds three values, so the fourth push links
+        // a new segment while the consumer may be draining the old one.
+        let queue = Arc::new(RawSpsc::new());
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 0..4 {
+                    unsafe { queue.push(i) };
+                }
+            })
+        };
+
+        let mut received = Vec::new();
+        while received.len() < 4 {
+            match unsafe { queue.pop() } {
+                Some(value) => received.push(value),
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+        assert_eq!(received, [0, 1, 2, 3]);
+    });
+}
+
+#[test]
+fn unbounded_segment_recycling() {
+    loom::model(|| {
+        // Start with a full first segment whose indices have already moved,
+        // then let the consumer drain it into the spare pool while the
+        // producer fills a second segment. Depending on the interleaving, a
+        // later push links the first segment again or a fresh one.
+        let queue = Arc::new(RawSpsc::new());
+        for i in 0..3 {
+            unsafe { queue.push(i) };
+        }
+        assert_eq!(unsafe { queue.pop() }, Some(0));
+        unsafe { queue.push(3) };
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 4..9 {
+                    unsafe { queue.push(i) };
+                }
+            })
+        };
+
+        let mut received = Vec::new();
+        while received.len() < 4 {
+            match unsafe { queue.pop() } {
+                Some(value) => received.push(value),
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+        received.extend(std::iter::from_fn(|| unsafe { queue.pop() }));
+        assert_eq!(received, [1, 2, 3, 4, 5, 6, 7, 8]);
+    });
+}
+
+#[test]
+fn unbounded_shrink_races_spare_reuse() {
+    loom::model(|| {
+        // Leave a drained segment in the spare pool, then let the consumer
+        // shrink the pool while the producer fills the tail and reaches for
+        // that spare. Exactly one of them must end up owning it.
+        let queue = Arc::new(RawSpsc::new());
+        for i in 0..4 {
+            unsafe { queue.push(i) };
+        }
+        for i in 0..4 {
+            assert_eq!(unsafe { queue.pop() }, Some(i));
+        }
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 4..8 {
+                    unsafe { queue.push(i) };
+                }
+            })
+        };
+
+        unsafe { queue.shrink() };
+        producer.join().unwrap();
+        let received: Vec<_> = std::iter::from_fn(|| unsafe { queue.pop() }).collect();
+        assert_eq!(received, [4, 5, 6, 7]);
+        // The tail, and either the spare or its replacement, drained in turn.
+        assert_eq!(queue.segments_held(), 2);
+    });
+}
+
+#[test]
+fn unbounded_drop_with_pending_items() {
+    loom::model(|| {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let queue = Arc::new(RawSpsc::new());
+        let producer = {
+            let (queue, drops) = (Arc::clone(&queue), Arc::clone(&drops));
+            thread::spawn(move || {
+                for _ in 0..2 {
+                    unsafe { queue.push(DropCounter(Arc::clone(&drops))) };
+                }
+            })
+        };
+
+        drop(unsafe { queue.pop() });
+        producer.join().unwrap();
+        drop(queue);
+        assert_eq!(drops.load(Relaxed), 2);
+    });
+}
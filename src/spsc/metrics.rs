@@ -0,0 +1,360 @@
+//! Per-channel counters, compiled in with the `metrics` feature.
+//!
+//! Each half of a channel owns one set of counters and is the only thread
+//! that writes to it, so counters are bumped with a plain relaxed load and
+//! store instead of a locked read-modify-write. The producer's and the
+//! consumer's counters live on separate cache lines, next to nothing else,
+//! so counting adds no cross-core traffic of its own. The one exception is
+//! the unbounded channel's high-water mark: that queue has no cheap length,
+//! so the producer derives it from the consumer's receive count. It keeps the
+//! last count it read and only reads it again when a send could set a new
+//! mark, since receives can only have lowered the depth since then.
+//!
+//! Without the feature the counter types are zero-sized and every method is
+//! an empty inline function, so the channels compile to the same code as if
+//! metrics did not exist. With it, a channel built with
+//! [`ChannelBuilder::metrics(false)`](crate::spsc::builder::ChannelBuilder::metrics)
+//! skips counting at run time and reports zeroed counters.
+//!
+//! # Example
+//! ```
+//! # #[cfg(feature = "metrics")] {
+//! use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+//! use lock_free_spsc::spsc::metrics::encode_text;
+//!
+//! let (tx, rx) = BoundedSpscChannel::split(4);
+//! tx.send(1).unwrap();
+//! rx.recv();
+//! rx.recv();
+//!
+//! let stats = rx.stats();
+//! assert_eq!((stats.sends, stats.recvs, stats.recv_empty), (1, 1, 1));
+//! assert!(encode_text([("orders", &stats)]).contains("spsc_sends_total{channel=\"orders\"} 1"));
+//! # }
+//! ```
+
+#[cfg(feature = "metrics")]
+use crate::cache_padded::CachePadded;
+#[cfg(feature = "metrics")]
+use std::fmt::Write;
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::time::Duration;
+
+/// A point-in-time copy of a channel's counters.
+///
+/// The counters are read one by one while the channel keeps running, so the
+/// snapshot is not atomic as a whole.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// Values successfully sent.
+    pub sends: u64,
+    /// Values successfully received.
+    pub recvs: u64,
+    /// Send attempts that failed because the channel was full.
+    pub send_full: u64,
+    /// Receive attempts that found the channel empty.
+    pub recv_empty: u64,
+    /// Largest number of queued values observed right after a send.
+    pub high_water_mark: u64,
+    /// Segments allocated by the unbounded queue (always zero when bounded).
+    pub segments_allocated: u64,
+    /// Spare segments the unbounded queue reused instead of allocating
+    /// (always zero when bounded).
+    pub segments_recycled: u64,
+    /// Segments the unbounded queue freed rather than kept as spares
+    /// (always zero when bounded).
+    pub segments_freed: u64,
+    /// Total time the sender spent parked in blocking operations.
+    pub send_parked: Duration,
+    /// Total time the receiver spent parked in blocking operations.
+    pub recv_parked: Duration,
+}
+
+/// Renders the stats of several channels in the Prometheus text exposition format.
+///
+/// Each metric gets a single `# TYPE` line followed by one sample per channel,
+/// labelled with `channel="<name>"`.
+#[cfg(feature = "metrics")]
+pub fn encode_text<'a>(channels: impl IntoIterator<Item = (&'a str, &'a ChannelStats)>) -> String {
+    type Field = fn(&ChannelStats) -> f64;
+    const METRICS: [(&str, &str, Field); 10] = [
+        ("spsc_sends_total", "counter", |s| s.sends as f64),
+        ("spsc_recvs_total", "counter", |s| s.recvs as f64),
+        ("spsc_send_full_total", "counter", |s| s.send_full as f64),
+        ("spsc_recv_empty_total", "counter", |s| s.recv_empty as f64),
+        ("spsc_queue_depth_high_water", "gauge", |s| {
+            s.high_water_mark as f64
+        }),
+        ("spsc_segments_allocated_total", "counter", |s| {
+            s.segments_allocated as f64
+        }),
+        ("spsc_segments_recycled_total", "counter", |s| {
+            s.segments_recycled as f64
+        }),
+        ("spsc_segments_freed_total", "counter", |s| {
+            s.segments_freed as f64
+        }),
+        ("spsc_send_parked_seconds_total", "counter", |s| {
+            s.send_parked.as_secs_f64()
+        }),
+        ("spsc_recv_parked_seconds_total", "counter", |s| {
+            s.recv_parked.as_secs_f64()
+        }),
+    ];
+
+    let channels: Vec<_> = channels.into_iter().collect();
+    let mut out = String::new();
+    for (metric, kind, field) in METRICS {
+        // Writing into a `String` cannot fail.
+        let _ = writeln!(out, "# TYPE {metric} {kind}");
+        for (name, stats) in &channels {
+            let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+            let _ = writeln!(out, "{metric}{{channel=\"{name}\"}} {}", field(stats));
+        }
+    }
+    out
+}
+
+#[cfg(feature = "metrics")]
+#[inline(always)]
+fn bump(counter: &AtomicU64, by: u64) {
+    // Single writer: a load and a store are enough and avoid a locked RMW.
+    counter.store(counter.load(Relaxed) + by, Relaxed);
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct ProducerInner {
+    enabled: bool,
+    sends: AtomicU64,
+    send_full: AtomicU64,
+    high_water_mark: AtomicU64,
+    parked_nanos: AtomicU64,
+    /// The consumer's receive count as of the last time the producer read it.
+    recvs_seen: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct ConsumerInner {
+    enabled: bool,
+    recvs: AtomicU64,
+    recv_empty: AtomicU64,
+    parked_nanos: AtomicU64,
+}
+
+/// Counters written only by the sending half of a channel.
+pub(crate) struct ProducerCounters {
+    #[cfg(feature = "metrics")]
+    inner: CachePadded<ProducerInner>,
+}
+
+/// Counters written only by the receiving half of a channel.
+pub(crate) struct ConsumerCounters {
+    #[cfg(feature = "metrics")]
+    inner: CachePadded<ConsumerInner>,
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+impl ProducerCounters {
+    /// Creates counters that count only if `enabled`.
+    pub(crate) fn new(enabled: bool) -> Self {
+        ProducerCounters {
+            #[cfg(feature = "metrics")]
+            inner: CachePadded::new(ProducerInner { enabled, ..Default::default() }),
+        }
+    }
+
+    /// Records a successful send that left `depth` values queued.
+    #[inline(always)]
+    pub(crate) fn sent(&self, depth: impl FnOnce() -> usize) {
+        #[cfg(feature = "metrics")]
+        if self.inner.enabled {
+            bump(&self.inner.sends, 1);
+            let depth = depth() as u64;
+            if depth > self.inner.high_water_mark.load(Relaxed) {
+                self.inner.high_water_mark.store(depth, Relaxed);
+            }
+        }
+    }
+
+    /// Records a successful send on a queue whose depth is the number of
+    /// sends minus the number of receives counted by `consumer`.
+    ///
+    /// `consumer` is only read when the send could raise the high-water mark.
+    #[inline(always)]
+    pub(crate) fn sent_counted(&self, consumer: &ConsumerCounters) {
+        #[cfg(feature = "metrics")]
+        if self.inner.enabled {
+            let sends = self.inner.sends.load(Relaxed) + 1;
+            self.inner.sends.store(sends, Relaxed);
+            let high = self.inner.high_water_mark.load(Relaxed);
+            // Receives only grow, so the depth is at most this.
+            if sends - self.inner.recvs_seen.load(Relaxed) > high {
+                let recvs = consumer.inner.recvs.load(Relaxed);
+                self.inner.recvs_seen.store(recvs, Relaxed);
+                let depth = sends.saturating_sub(recvs);
+                if depth > high {
+                    self.inner.high_water_mark.store(depth, Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Records a send that failed because the channel was full.
+    #[inline(always)]
+    pub(crate) fn full(&self) {
+        #[cfg(feature = "metrics")]
+        if self.inner.enabled {
+            bump(&self.inner.send_full, 1);
+        }
+    }
+
+    /// Records time spent parked by the sender.
+    #[inline(always)]
+    pub(crate) fn parked(&self, duration: Duration) {
+        #[cfg(feature = "metrics")]
+        if self.inner.enabled {
+            bump(&self.inner.parked_nanos, duration.as_nanos() as u64);
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+impl ConsumerCounters {
+    /// Creates counters that count only if `enabled`.
+    pub(crate) fn new(enabled: bool) -> Self {
+        ConsumerCounters {
+            #[cfg(feature = "metrics")]
+            inner: CachePadded::new(ConsumerInner { enabled, ..Default::default() }),
+        }
+    }
+
+    /// Records a successful receive.
+    #[inline(always)]
+    pub(crate) fn received(&self) {
+        #[cfg(feature = "metrics")]
+        if self.inner.enabled {
+            bump(&self.inner.recvs, 1);
+        }
+    }
+
+    /// Records a receive that found the channel empty.
+    #[inline(always)]
+    pub(crate) fn empty(&self) {
+        #[cfg(feature = "metrics")]
+        if self.inner.enabled {
+            bump(&self.inner.recv_empty, 1);
+        }
+    }
+
+    /// Records time spent parked by the receiver.
+    #[inline(always)]
+    pub(crate) fn parked(&self, duration: Duration) {
+        #[cfg(feature = "metrics")]
+        if self.inner.enabled {
+            bump(&self.inner.parked_nanos, duration.as_nanos() as u64);
+        }
+    }
+}
+
+/// Builds a snapshot from both halves' counters.
+#[cfg(feature = "metrics")]
+pub(crate) fn snapshot(producer: &ProducerCounters, consumer: &ConsumerCounters) -> ChannelStats {
+    let (p, c) = (&*producer.inner, &*consumer.inner);
+    ChannelStats {
+        sends: p.sends.load(Relaxed),
+        recvs: c.recvs.load(Relaxed),
+        send_full: p.send_full.load(Relaxed),
+        recv_empty: c.recv_empty.load(Relaxed),
+        high_water_mark: p.high_water_mark.load(Relaxed),
+        segments_allocated: 0,
+        segments_recycled: 0,
+        segments_freed: 0,
+        send_parked: Duration::from_nanos(p.parked_nanos.load(Relaxed)),
+        recv_parked: Duration::from_nanos(c.parked_nanos.load(Relaxed)),
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::encode_text;
+    use crate::spsc::bounded_spsc::BoundedSpscChannel;
+    use crate::spsc::unbounded_spsc::UnboundSpscChannel;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn bounded_counters() {
+        let (sender, receiver) = BoundedSpscChannel::split(2);
+        assert_eq!(receiver.recv(), None);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        assert!(sender.send(3).is_err());
+        assert_eq!(receiver.recv(), Some(1));
+
+        let stats = sender.stats();
+        assert_eq!(stats, receiver.stats());
+        assert_eq!(stats.sends, 2);
+        assert_eq!(stats.recvs, 1);
+        assert_eq!(stats.send_full, 1);
+        assert_eq!(stats.recv_empty, 1);
+        assert_eq!(stats.high_water_mark, 2);
+        assert_eq!(stats.segments_allocated, 0);
+    }
+
+    #[test]
+    fn unbounded_counters() {
+        let (sender, receiver) = UnboundSpscChannel::split();
+        (&sender).extend(0..1000);
+        for _ in 0..400 {
+            receiver.recv().unwrap();
+        }
+        let stats = receiver.stats();
+        assert_eq!((stats.sends, stats.recvs), (1000, 400));
+        assert_eq!(stats.high_water_mark, 1000);
+        assert!(stats.segments_allocated > 1);
+        // Three segments drained: the first is kept as a spare.
+        assert_eq!(stats.segments_freed, 2);
+        assert_eq!(stats.segments_recycled, 0);
+    }
+
+    #[test]
+    fn unbounded_high_water_mark_follows_receives() {
+        let (sender, receiver) = UnboundSpscChannel::split();
+        (&sender).extend(0..3);
+        receiver.recv().unwrap();
+        receiver.recv().unwrap();
+        (&sender).extend(0..2); // depth 3, not a new mark
+        assert_eq!(sender.stats().high_water_mark, 3);
+        (&sender).extend(0..2);
+        assert_eq!(sender.stats().high_water_mark, 5);
+    }
+
+    #[test]
+    fn parked_time_is_recorded() {
+        let (sender, receiver) = BoundedSpscChannel::split(1);
+        let producer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            sender.send(1).unwrap();
+        });
+        assert_eq!(receiver.recv_blocking(), Some(1));
+        producer.join().unwrap();
+        assert!(receiver.stats().recv_parked > Duration::ZERO);
+    }
+
+    #[test]
+    fn text_exposition() {
+        let (sender, receiver) = BoundedSpscChannel::split(4);
+        sender.send(()).unwrap();
+        let a = sender.stats();
+        let b = receiver.stats();
+        let text = encode_text([("a", &a), ("b \"quoted\"", &b)]);
+        assert_eq!(text.matches("# TYPE spsc_sends_total counter").count(), 1);
+        assert!(text.contains("spsc_sends_total{channel=\"a\"} 1\n"));
+        assert!(text.contains("spsc_sends_total{channel=\"b \\\"quoted\\\"\"} 1\n"));
+        assert!(text.contains("# TYPE spsc_queue_depth_high_water gauge"));
+    }
+}
@@ -1,2 +1,20 @@
+pub mod alloc;
 pub mod bounded_spsc;
+pub mod builder;
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub mod differential;
+pub(crate) mod drop_guard;
+pub mod error;
+pub mod fan_in;
+#[cfg(test)]
+mod history;
+pub mod metrics;
+pub mod observer;
+pub mod priority;
+pub mod select;
 pub mod unbounded_spsc;
+pub(crate) mod waiter;
+
+#[cfg(all(test, loom))]
+mod loom_tests;
@@ -0,0 +1,159 @@
+//! Hooks for observing channel events.
+//!
+//! An [`SpscObserver`] is attached when a channel is created and is called
+//! synchronously by whichever half triggers the event, on that half's thread.
+//! Every channel handle carries the observer as a generic parameter that
+//! defaults to [`NoopObserver`], whose empty callbacks are inlined away, so
+//! channels created without an observer pay nothing for the hooks.
+//!
+//! # Example
+//! ```
+//! use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+//! use lock_free_spsc::spsc::observer::SpscObserver;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! #[derive(Default)]
+//! struct CountFull(AtomicUsize);
+//!
+//! impl SpscObserver for CountFull {
+//!     fn on_full(&self) {
+//!         self.0.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let (tx, _rx) = BoundedSpscChannel::split_with_observer(1, CountFull::default());
+//! tx.send(1).unwrap();
+//! assert!(tx.send(2).is_err());
+//! assert_eq!(tx.observer().0.load(Ordering::Relaxed), 1);
+//! ```
+
+/// Identifies which half of a channel an event came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The sending half.
+    Sender,
+    /// The receiving half.
+    Receiver,
+}
+
+/// Callbacks invoked on channel events.
+///
+/// Every method has an empty default, so implementors only override the
+/// events they care about. Callbacks run inline on the hot path of the
+/// thread that triggered them and should be cheap; they must not call back
+/// into the same channel.
+pub trait SpscObserver: Send + Sync {
+    /// A value was sent.
+    #[inline(always)]
+    fn on_send(&self) {}
+
+    /// A value was received.
+    #[inline(always)]
+    fn on_recv(&self) {}
+
+    /// A send failed because the channel was full.
+    #[inline(always)]
+    fn on_full(&self) {}
+
+    /// A receive found the channel empty.
+    #[inline(always)]
+    fn on_empty_poll(&self) {}
+
+    /// The unbounded queue allocated a new segment.
+    #[inline(always)]
+    fn on_segment_alloc(&self) {}
+
+    /// One half of the channel was dropped.
+    #[inline(always)]
+    fn on_disconnect(&self, side: Side) {
+        let _ = side;
+    }
+}
+
+/// The default observer, which ignores every event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopObserver;
+
+impl SpscObserver for NoopObserver {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Side, SpscObserver};
+    use crate::spsc::bounded_spsc::BoundedSpscChannel;
+    use crate::spsc::unbounded_spsc::UnboundSpscChannel;
+    use std::sync::Mutex;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        Send,
+        Recv,
+        Full,
+        Empty,
+        SegmentAlloc,
+        Disconnect(Side),
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<Event>>);
+
+    impl Recorder {
+        fn take(&self) -> Vec<Event> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    impl SpscObserver for Recorder {
+        fn on_send(&self) {
+            self.0.lock().unwrap().push(Event::Send);
+        }
+        fn on_recv(&self) {
+            self.0.lock().unwrap().push(Event::Recv);
+        }
+        fn on_full(&self) {
+            self.0.lock().unwrap().push(Event::Full);
+        }
+        fn on_empty_poll(&self) {
+            self.0.lock().unwrap().push(Event::Empty);
+        }
+        fn on_segment_alloc(&self) {
+            self.0.lock().unwrap().push(Event::SegmentAlloc);
+        }
+        fn on_disconnect(&self, side: Side) {
+            self.0.lock().unwrap().push(Event::Disconnect(side));
+        }
+    }
+
+    #[test]
+    fn bounded_events() {
+        let (sender, receiver) = BoundedSpscChannel::split_with_observer(1, Recorder::default());
+        sender.send(1).unwrap();
+        assert!(sender.send(2).is_err());
+        assert_eq!(receiver.recv(), Some(1));
+        assert_eq!(receiver.recv(), None);
+        drop(sender);
+        assert_eq!(
+            receiver.observer().take(),
+            [
+                Event::Send,
+                Event::Full,
+                Event::Recv,
+                Event::Empty,
+                Event::Disconnect(Side::Sender)
+            ]
+        );
+    }
+
+    #[test]
+    fn unbounded_segment_alloc_and_disconnect() {
+        let (sender, receiver) = UnboundSpscChannel::split_with_observer(Recorder::default());
+        (&sender).extend(0..1000);
+        let events = sender.observer().take();
+        assert_eq!(events.iter().filter(|e| **e == Event::Send).count(), 1000);
+        assert!(events.contains(&Event::SegmentAlloc));
+        drop(receiver);
+        assert_eq!(
+            sender.observer().take(),
+            [Event::Disconnect(Side::Receiver)]
+        );
+    }
+}
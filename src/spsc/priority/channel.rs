@@ -0,0 +1,138 @@
+//! Single-producer single-consumer channel with several priority levels.
+//!
+//! Every level is an independent [`BoundedSpsc`] ring, so urgent messages
+//! never queue up behind bulk traffic sent earlier on a lower level. The
+//! receiver always drains the highest non-empty level first; level `0` is the
+//! highest priority.
+//!
+//! # Example
+//! ```
+//! use lock_free_spsc::spsc::priority::PriorityChannel;
+//!
+//! let (tx, mut rx) = PriorityChannel::split(2, 16);
+//!
+//! tx.send(1, "bulk").unwrap();
+//! tx.send(0, "control").unwrap();
+//!
+//! assert_eq!(rx.recv(), Some("control"));
+//! assert_eq!(rx.recv(), Some("bulk"));
+//! ```
+//!
+//! # Anti-starvation
+//! Strict priority can starve the lower levels forever. With
+//! [`Receiver::set_anti_starvation`], every `n`-th item is taken from below the
+//! highest non-empty level whenever such an item is available.
+
+use crate::spsc::bounded_spsc::inner_spsc::BoundedSpsc;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Entry point for splitting a priority channel into its sender and receiver halves.
+pub struct PriorityChannel;
+
+impl PriorityChannel {
+    /// Creates a channel with `levels` priority levels, each holding up to
+    /// `capacity_per_level` items.
+    ///
+    /// # Panics
+    /// Panics if `levels` is zero.
+    pub fn split<T>(levels: usize, capacity_per_level: usize) -> (Sender<T>, Receiver<T>) {
+        assert!(levels > 0, "a priority channel needs at least one level");
+        let levels = (0..levels)
+            .map(|_| BoundedSpsc::new(capacity_per_level))
+            .collect();
+        let sender = Sender {
+            levels: Arc::new(levels),
+            _not_sync: PhantomData,
+        };
+        let receiver = Receiver {
+            levels: Arc::clone(&sender.levels),
+            anti_starvation: None,
+            streak: 0,
+        };
+        (sender, receiver)
+    }
+}
+
+/// The sending half of a priority channel.
+pub struct Sender<T> {
+    levels: Arc<Box<[BoundedSpsc<T>]>>,
+    /// `!Sync`: every level allows a single producer thread.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Sender<T> {
+    /// Attempts to send a value on the given priority level.
+    ///
+    /// Returns `Err(value)` if that level is full; other levels are unaffected.
+    ///
+    /// # Panics
+    /// Panics if `priority` is not smaller than [`levels`](Self::levels).
+    #[inline(always)]
+    pub fn send(&self, priority: usize, value: T) -> Result<(), T> {
+        // The sender is neither `Clone` nor `Sync`, so it is the only producer.
+        unsafe { self.levels[priority].push(value) }
+    }
+
+    /// Returns `true` if the given priority level is currently full.
+    #[inline(always)]
+    pub fn is_full(&self, priority: usize) -> bool {
+        self.levels[priority].is_full()
+    }
+
+    /// Returns the number of priority levels.
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+}
+
+/// The receiving half of a priority channel.
+pub struct Receiver<T> {
+    levels: Arc<Box<[BoundedSpsc<T>]>>,
+    anti_starvation: Option<usize>,
+    streak: usize,
+}
+
+impl<T> Receiver<T> {
+    /// Attempts to receive the most urgent available value.
+    ///
+    /// Returns `None` if every level is empty.
+    pub fn recv(&mut self) -> Option<T> {
+        let top = self.levels.iter().position(|level| !level.is_empty())?;
+        let starving = self
+            .anti_starvation
+            .is_some_and(|every| self.streak >= every);
+        // The receiver is the only consumer of every level.
+        let mut lower = self.levels[top + 1..].iter();
+        if starving && let Some(value) = lower.find_map(|level| unsafe { level.pop() }) {
+            self.streak = 0;
+            return Some(value);
+        }
+        // Only the consumer pops, so a level seen non-empty stays non-empty.
+        let value = unsafe { self.levels[top].pop() }?;
+        self.streak += 1;
+        Some(value)
+    }
+
+    /// Takes one item from a lower level after every `every` items, as long
+    /// as a lower level has something waiting. `None` restores strict priority.
+    ///
+    /// # Panics
+    /// Panics if `every` is `Some(0)`.
+    pub fn set_anti_starvation(&mut self, every: Option<usize>) {
+        assert_ne!(every, Some(0), "anti-starvation period must be non-zero");
+        self.anti_starvation = every;
+        self.streak = 0;
+    }
+
+    /// Returns `true` if every level is empty.
+    pub fn is_empty(&self) -> bool {
+        self.levels.iter().all(BoundedSpsc::is_empty)
+    }
+
+    /// Returns the number of priority levels.
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+}
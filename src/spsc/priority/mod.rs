@@ -0,0 +1,66 @@
+mod channel;
+
+pub use channel::{PriorityChannel, Receiver, Sender};
+
+#[cfg(test)]
+mod tests {
+    use super::PriorityChannel;
+    use std::thread;
+
+    #[test]
+    fn highest_level_first() {
+        let (sender, mut receiver) = PriorityChannel::split(3, 4);
+        sender.send(2, 'c').unwrap();
+        sender.send(1, 'b').unwrap();
+        sender.send(0, 'a').unwrap();
+        sender.send(2, 'd').unwrap();
+        let order: Vec<_> = std::iter::from_fn(|| receiver.recv()).collect();
+        assert_eq!(order, ['a', 'b', 'c', 'd']);
+        assert!(receiver.is_empty());
+    }
+
+    #[test]
+    fn levels_fill_independently() {
+        let (sender, mut receiver) = PriorityChannel::split(2, 1);
+        assert!(sender.send(1, 10).is_ok());
+        assert!(sender.send(1, 11).is_err()); // Low level is full
+        assert!(sender.send(0, 1).is_ok()); // High level still accepts
+        assert_eq!(receiver.recv(), Some(1));
+        assert_eq!(receiver.recv(), Some(10));
+    }
+
+    #[test]
+    fn anti_starvation_serves_lower_levels() {
+        let (sender, mut receiver) = PriorityChannel::split(2, 16);
+        receiver.set_anti_starvation(Some(3));
+        for i in 0..8 {
+            sender.send(0, i).unwrap();
+        }
+        sender.send(1, 100).unwrap();
+        sender.send(1, 101).unwrap();
+        let order: Vec<_> = std::iter::from_fn(|| receiver.recv()).collect();
+        assert_eq!(order, [0, 1, 2, 100, 3, 4, 5, 101, 6, 7]);
+    }
+
+    #[test]
+    fn multithreaded_priority() {
+        let (sender, mut receiver) = PriorityChannel::split(4, 64);
+        let t = thread::spawn(move || {
+            for i in 0..10_000u64 {
+                while sender.send((i % 4) as usize, i).is_err() {} // retry if full
+            }
+        });
+
+        let mut sum = 0;
+        for _ in 0..10_000 {
+            loop {
+                if let Some(v) = receiver.recv() {
+                    sum += v;
+                    break;
+                }
+            }
+        }
+        t.join().unwrap();
+        assert_eq!(sum, (0..10_000u64).sum());
+    }
+}
@@ -0,0 +1,220 @@
+//! Waiting on several receivers at once.
+//!
+//! A [`Select`] is built from any mix of bounded and unbounded receivers and
+//! reports the index of one that is ready: it either holds a value or its
+//! sender has been dropped, so receiving from it will not wait. Selecting does
+//! not consume anything: the caller receives from the returned receiver
+//! afterwards, which is always possible because only the consumer thread can
+//! take items out.
+//!
+//! # Example
+//! ```
+//! use lock_free_spsc::spsc::bounded_spsc::BoundedSpscChannel;
+//! use lock_free_spsc::spsc::select::Select;
+//! use lock_free_spsc::spsc::unbounded_spsc::UnboundSpscChannel;
+//! use std::time::Duration;
+//!
+//! let (bounded_tx, bounded_rx) = BoundedSpscChannel::split::<u32>(8);
+//! let (unbounded_tx, unbounded_rx) = UnboundSpscChannel::split::<&str>();
+//!
+//! let mut select = Select::new()
+//!     .recv(&bounded_rx)
+//!     .recv(&unbounded_rx)
+//!     .timeout(Duration::from_millis(10));
+//! assert_eq!(select.try_select(), None);
+//!
+//! unbounded_tx.send("ping").unwrap();
+//! assert_eq!(select.select(), Some(1));
+//! assert_eq!(unbounded_rx.recv(), Some("ping"));
+//! # drop(bounded_tx);
+//! ```
+//!
+//! # Blocking
+//! When nothing is ready, the calling thread registers one waiter with every
+//! receiver's wait slot and parks; whichever sender publishes first wakes it.
+
+use crate::spsc::waiter::wait_until;
+use std::time::{Duration, Instant};
+
+pub(crate) mod sealed {
+    use crate::spsc::waiter::WaitSlot;
+
+    /// Hooks a receiver exposes to [`Select`](super::Select).
+    pub trait Selectable {
+        /// Returns `true` if a value is waiting or the sender has disconnected.
+        fn is_ready(&self) -> bool;
+
+        /// Returns the slot the sending side notifies after publishing.
+        fn rx_slot(&self) -> &WaitSlot;
+    }
+}
+
+/// A receiver that can take part in a [`Select`].
+///
+/// This trait is sealed and implemented for the receiving halves of the
+/// bounded and unbounded channels.
+pub trait SelectHandle: sealed::Selectable {}
+
+impl<S: sealed::Selectable + ?Sized> SelectHandle for S {}
+
+/// How [`Select`] chooses between several receivers that are ready at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fairness {
+    /// Start scanning after the receiver picked last time, so that a busy
+    /// receiver cannot starve the others.
+    #[default]
+    RoundRobin,
+    /// Always scan in registration order: earlier receivers win ties.
+    Biased,
+}
+
+/// Builder that waits until one of several receivers is ready.
+///
+/// Indices returned by the select methods refer to the order in which the
+/// receivers were added with [`recv`](Self::recv), starting at zero.
+#[derive(Default)]
+pub struct Select<'a> {
+    handles: Vec<&'a dyn SelectHandle>,
+    timeout: Option<Duration>,
+    fairness: Fairness,
+    next: usize,
+}
+
+impl<'a> Select<'a> {
+    /// Creates an empty select.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a receiver and returns the builder.
+    pub fn recv<R: SelectHandle>(mut self, receiver: &'a R) -> Self {
+        self.handles.push(receiver);
+        self
+    }
+
+    /// Sets the timeout used by [`select`](Self::select).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how ties between ready receivers are broken.
+    pub fn fairness(mut self, fairness: Fairness) -> Self {
+        self.fairness = fairness;
+        self
+    }
+
+    /// Returns the index of a ready receiver without blocking.
+    pub fn try_select(&mut self) -> Option<usize> {
+        let len = self.handles.len();
+        let start = match self.fairness {
+            Fairness::RoundRobin if len > 0 => self.next % len,
+            _ => 0,
+        };
+        let ready = (0..len)
+            .map(|step| (start + step) % len)
+            .find(|&idx| self.handles[idx].is_ready())?;
+        self.next = ready + 1;
+        Some(ready)
+    }
+
+    /// Blocks until a receiver is ready and returns its index.
+    ///
+    /// Returns `None` if a [`timeout`](Self::timeout) was configured and it
+    /// elapsed first, or right away if no receiver was added, since nothing
+    /// could ever become ready.
+    pub fn select(&mut self) -> Option<usize> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.select_deadline(deadline)
+    }
+
+    /// Blocks for at most `timeout` until a receiver is ready and returns its index.
+    ///
+    /// Like [`select`](Self::select), returns `None` right away if no receiver
+    /// was added.
+    pub fn select_timeout(&mut self, timeout: Duration) -> Option<usize> {
+        self.select_deadline(Some(Instant::now() + timeout))
+    }
+
+    fn select_deadline(&mut self, deadline: Option<Instant>) -> Option<usize> {
+        if self.handles.is_empty() {
+            return None; // No sender could ever wake us
+        }
+        let handles = self.handles.clone();
+        let slots: Vec<_> = handles.iter().map(|handle| handle.rx_slot()).collect();
+        wait_until(&slots, deadline, |_| {}, || self.try_select())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fairness, Select};
+    use crate::spsc::bounded_spsc::BoundedSpscChannel;
+    use crate::spsc::unbounded_spsc::UnboundSpscChannel;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn try_select_reports_ready_receiver() {
+        let (tx_a, rx_a) = BoundedSpscChannel::split::<u8>(4);
+        let (tx_b, rx_b) = UnboundSpscChannel::split::<u8>();
+        let mut select = Select::new().recv(&rx_a).recv(&rx_b);
+        assert_eq!(select.try_select(), None);
+        tx_b.send(1).unwrap();
+        assert_eq!(select.try_select(), Some(1));
+        assert_eq!(rx_b.recv(), Some(1));
+        tx_a.send(2).unwrap();
+        assert_eq!(select.try_select(), Some(0));
+    }
+
+    #[test]
+    fn fairness_policies() {
+        let (tx_a, rx_a) = BoundedSpscChannel::split::<u8>(4);
+        let (tx_b, rx_b) = BoundedSpscChannel::split::<u8>(4);
+        tx_a.send(0).unwrap();
+        tx_b.send(0).unwrap();
+
+        let mut biased = Select::new()
+            .recv(&rx_a)
+            .recv(&rx_b)
+            .fairness(Fairness::Biased);
+        assert_eq!(biased.try_select(), Some(0));
+        assert_eq!(biased.try_select(), Some(0));
+
+        let mut round_robin = Select::new().recv(&rx_a).recv(&rx_b);
+        assert_eq!(round_robin.try_select(), Some(0));
+        assert_eq!(round_robin.try_select(), Some(1));
+        assert_eq!(round_robin.try_select(), Some(0));
+    }
+
+    #[test]
+    fn timeout_elapses() {
+        let (_tx, rx) = UnboundSpscChannel::split::<u8>();
+        let mut select = Select::new().recv(&rx);
+        assert_eq!(select.select_timeout(Duration::from_millis(20)), None);
+        let mut select = Select::new().recv(&rx).timeout(Duration::from_millis(20));
+        assert_eq!(select.select(), None);
+    }
+
+    #[test]
+    fn empty_select_does_not_block() {
+        let mut select = Select::new();
+        assert_eq!(select.select(), None);
+        assert_eq!(select.select_timeout(Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn blocking_select_woken_by_any_sender() {
+        let (tx_a, rx_a) = BoundedSpscChannel::split::<u32>(4);
+        let (tx_b, rx_b) = UnboundSpscChannel::split::<u32>();
+        let producer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            tx_b.send(7).unwrap();
+            tx_a
+        });
+        let mut select = Select::new().recv(&rx_a).recv(&rx_b);
+        assert_eq!(select.select(), Some(1));
+        assert_eq!(rx_b.recv(), Some(7));
+        producer.join().unwrap();
+    }
+}
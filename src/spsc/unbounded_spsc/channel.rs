@@ -1,5 +1,25 @@
-use super::raw_spsc::RawSpsc;
+use super::limit::{Limit, LimitExceeded, Usage};
+use super::raw_spsc::{DEFAULT_SPARE_SEGMENTS, RawSpsc, SegmentSize};
+use crate::cache_padded::CachePadded;
+use crate::spsc::alloc::{Global, SpscAlloc};
+use crate::spsc::builder::Options;
+use crate::spsc::error::{ChannelError, ReuniteError, TrySendError};
+#[cfg(feature = "metrics")]
+use crate::spsc::metrics::{self, ChannelStats};
+use crate::spsc::metrics::{ConsumerCounters, ProducerCounters};
+use crate::spsc::observer::{NoopObserver, Side, SpscObserver};
+use crate::spsc::select::sealed;
+use crate::spsc::waiter::{WaitSlot, wait_with};
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
 use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicBool, AtomicUsize,
+    Ordering::{Acquire, Relaxed, Release},
+};
 
 /// Prevents Clone and Copy at compile time.
 #[derive(Debug)]
@@ -11,56 +31,532 @@ struct NoClone;
 /// This channel is unbounded and non-blocking. It provides one [`Sender`] and one [`Receiver`]
 /// which are safe to move across threads, but **must not be cloned**.
 /// Internally backed by a lock-free queue [`RawSpsc`].
+///
+/// # Thread safety
+/// The halves can only be sent to another thread when `T: Send`:
+///
+/// ```compile_fail
+/// use lock_free_spsc::spsc::unbounded_spsc::UnboundSpscChannel;
+/// use std::rc::Rc;
+///
+/// let (tx, _rx) = UnboundSpscChannel::split::<Rc<u8>>();
+/// std::thread::spawn(move || drop(tx));
+/// ```
+///
+/// ```compile_fail
+/// use lock_free_spsc::spsc::unbounded_spsc::UnboundSpscChannel;
+/// use std::rc::Rc;
+///
+/// let (_tx, rx) = UnboundSpscChannel::split::<Rc<u8>>();
+/// std::thread::spawn(move || drop(rx));
+/// ```
 pub struct UnboundSpscChannel;
 
+/// The two halves of a channel, as returned by the constructors.
+type Halves<T, O, A> = (Sender<T, O, A>, Receiver<T, O, A>);
+
+/// State shared by both halves: the segmented queue, its limit and the
+/// weight that went in and out of it, the idle-shrink policy, the slots a
+/// blocked half parks on, whether each half has been dropped, each half's
+/// metrics counters, the observer, and the options it was built with.
+struct Shared<T, O, A: SpscAlloc> {
+    queue: RawSpsc<T, A>,
+    limit: Limit<T>,
+    /// Shrink once the queue fits in one segment for this many receives.
+    shrink_after: Option<usize>,
+    /// Receives in a row that found the queue within one segment, written
+    /// only by the consumer.
+    idle_ops: AtomicUsize,
+    /// Total weight sent, written only by the producer.
+    weight_sent: CachePadded<AtomicUsize>,
+    /// Total weight received, written only by the consumer.
+    weight_received: CachePadded<AtomicUsize>,
+    rx_slot: WaitSlot,
+    tx_slot: WaitSlot,
+    tx_closed: AtomicBool,
+    rx_closed: AtomicBool,
+    producer: ProducerCounters,
+    consumer: ConsumerCounters,
+    observer: O,
+    options: Options,
+}
+
+impl<T, O, A: SpscAlloc> Shared<T, O, A> {
+    fn usage(&self) -> Usage {
+        // Sent before received: the weight of a value is added before it is
+        // pushed, so the difference cannot go below zero.
+        let sent = self.weight_sent.load(Acquire);
+        Usage {
+            segments: self.queue.segments_held(),
+            bytes: self.queue.footprint(),
+            weight: sent.wrapping_sub(self.weight_received.load(Acquire)),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            segments_allocated: self.queue.segments_allocated() as u64,
+            segments_recycled: self.queue.segments_recycled() as u64,
+            segments_freed: self.queue.segments_freed() as u64,
+            ..metrics::snapshot(&self.producer, &self.consumer)
+        }
+    }
+}
+
 /// The sending half of an [`UnboundSpscChannel`].
 ///
-/// This struct wraps an atomic reference to the underlying queue.  
-/// Only a single instance should exist—cloning or sharing between multiple producers is **undefined behavior**.
+/// This struct wraps an atomic reference to the underlying queue.
+/// It cannot be cloned, and it can be moved to another thread but not shared
+/// with one, so there is never more than one producer:
+///
+/// ```compile_fail
+/// use lock_free_spsc::spsc::unbounded_spsc::UnboundSpscChannel;
+/// use std::sync::Arc;
+///
+/// let (tx, _rx) = UnboundSpscChannel::split::<u8>();
+/// let tx = Arc::new(tx);
+/// let second = Arc::clone(&tx);
+/// std::thread::spawn(move || second.send(1));
+/// ```
 #[repr(transparent)]
-pub struct Sender<T> {
-    inner: Arc<RawSpsc<T>>,
+pub struct Sender<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    inner: Arc<Shared<T, O, A>>,
     _no_clone: NoClone,
+    /// `!Sync`: the queue allows a single producer thread.
+    _not_sync: PhantomData<Cell<()>>,
 }
 
 /// The receiving half of an [`UnboundSpscChannel`].
 ///
-/// Like [`Sender`], this should not be cloned. Only one thread should consume from the channel.
+/// Like [`Sender`], this cannot be cloned or shared between threads, so only
+/// one thread consumes from the channel.
 #[repr(transparent)]
-pub struct Receiver<T> {
-    inner: Arc<RawSpsc<T>>,
+pub struct Receiver<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    inner: Arc<Shared<T, O, A>>,
     _no_clone: NoClone,
+    /// `!Sync`: the queue allows a single consumer thread.
+    _not_sync: PhantomData<Cell<()>>,
 }
 
-impl<T> Sender<T> {
+impl<T, O: SpscObserver, A: SpscAlloc> Sender<T, O, A> {
     /// Sends a value into the channel.
     ///
+    /// Without a [`Limit`] this always succeeds. Once the limit is reached,
+    /// the value is handed back in a [`LimitExceeded`], or, if the limit is
+    /// [`blocking`](Limit::blocking), the thread parks until the receiver
+    /// makes room.
+    ///
     /// # Panics
-    /// This function does not panic under normal usage.
+    /// Panics if the value needs a new segment and it cannot be allocated;
+    /// use [`try_send`](Self::try_send) to handle that case.
+    #[inline]
+    pub fn send(&self, value: T) -> Result<(), LimitExceeded<T>> {
+        if self.inner.limit.blocking {
+            return self.send_blocking(value);
+        }
+        self.push(value).or_else(Self::limit_exceeded)
+    }
+
+    /// Sends a value without ever blocking or panicking.
     ///
-    /// # Safety
-    /// The channel must follow the SPSC model—only one sender thread must exist.
+    /// Fails with [`TrySendError::Full`] when the [`Limit`] is reached, even
+    /// if it is [`blocking`](Limit::blocking), and with
+    /// [`TrySendError::Alloc`] when the value needs a new segment that cannot
+    /// be allocated. Either way the channel is left as it was and the value
+    /// is handed back.
     #[inline]
-    pub fn send(&self, value: T) {
-        self.inner.push(value);
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.push(value)
+    }
+
+    /// Turns a failed [`push`](Self::push) into the error of `send`,
+    /// panicking if a segment could not be allocated.
+    fn limit_exceeded(err: TrySendError<T>) -> Result<(), LimitExceeded<T>> {
+        match err {
+            TrySendError::Full(value) => Err(LimitExceeded(value)),
+            TrySendError::Alloc(_, err) => panic!("{err}"),
+        }
+    }
+
+    /// Sends a value if the [`Limit`] allows it and its segment can be
+    /// allocated, without blocking.
+    #[inline]
+    fn push(&self, value: T) -> Result<(), TrySendError<T>> {
+        let Some(weight) = self.admit(&value) else {
+            self.inner.producer.full();
+            self.inner.observer.on_full();
+            return Err(TrySendError::Full(value));
+        };
+        let sent = self.inner.weight_sent.load(Relaxed);
+        if weight != 0 {
+            // Published by the push below, so the receiver never sees a value
+            // before its weight.
+            self.inner.weight_sent.store(sent.wrapping_add(weight), Release);
+        }
+        // The sender is neither `Clone` nor `Sync`, so it is the only producer.
+        match unsafe { self.inner.queue.try_push(value) } {
+            Ok(true) => self.inner.observer.on_segment_alloc(),
+            Ok(false) => {}
+            Err((value, err)) => {
+                // Taken back before anyone could receive the value.
+                self.inner.weight_sent.store(sent, Release);
+                return Err(TrySendError::Alloc(value, err));
+            }
+        }
+        // The queue has no cheap length, so the depth comes from the counters.
+        self.inner.producer.sent_counted(&self.inner.consumer);
+        self.inner.observer.on_send();
+        self.inner.rx_slot.notify();
+        Ok(())
+    }
+
+    /// Parks while the channel is at its limit.
+    ///
+    /// Gives the value back if it could never be received, because the
+    /// [`Receiver`] has been dropped or the value outweighs the whole limit.
+    fn send_blocking(&self, value: T) -> Result<(), LimitExceeded<T>> {
+        if let Some(max) = self.inner.limit.max_weight
+            && (self.inner.limit.weigh)(&value) > max
+        {
+            return Err(LimitExceeded(value));
+        }
+        let mut value = Some(value);
+        let parked = |duration| self.inner.producer.parked(duration);
+        wait_with(self.inner.options.wait, &[&self.inner.tx_slot], parked, || {
+            let pending = value.take()?;
+            match self.push(pending) {
+                Ok(()) => Some(Ok(())),
+                // Nothing will make room any more.
+                Err(full) if self.inner.rx_closed.load(Acquire) => {
+                    Some(Self::limit_exceeded(full))
+                }
+                Err(TrySendError::Full(pending)) => {
+                    value = Some(pending);
+                    None
+                }
+                Err(alloc) => Some(Self::limit_exceeded(alloc)),
+            }
+        })
+    }
+
+    /// Checks `value` against the limit, returning its weight if it fits.
+    ///
+    /// Only the receiver lowers the usage, so a value that fits now still
+    /// fits when it is pushed.
+    #[inline]
+    fn admit(&self, value: &T) -> Option<usize> {
+        let limit = &self.inner.limit;
+        let weight = (limit.weigh)(value);
+        if let Some(max) = limit.max_weight
+            && weight > max.saturating_sub(self.inner.usage().weight)
+        {
+            return None;
+        }
+        if let Some(max) = limit.max_segments
+            && self.inner.queue.segments_held() >= max
+            // The sender is the only producer.
+            && unsafe { self.inner.queue.push_adds_segment() }
+        {
+            return None;
+        }
+        Some(weight)
+    }
+
+    /// Returns how much of its [`Limit`] the channel is using.
+    pub fn usage(&self) -> Usage {
+        self.inner.usage()
+    }
+
+    /// Returns `true` if the [`Receiver`] has been dropped.
+    ///
+    /// Sending still succeeds afterwards, up to the channel's [`Limit`]; the
+    /// values are dropped with the channel.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.rx_closed.load(Acquire)
+    }
+
+    /// Returns the observer attached to the channel.
+    pub fn observer(&self) -> &O {
+        &self.inner.observer
+    }
+
+    /// Returns the name the channel was [built](crate::spsc::builder) with.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.options.name.as_deref()
+    }
+
+    /// Returns a snapshot of the channel's counters.
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> ChannelStats {
+        self.inner.stats()
+    }
+
+    /// Puts the two halves of a channel back together, so that the values
+    /// still queued in it can be taken out instead of dropped.
+    ///
+    /// Fails, handing both halves back, if `rx` belongs to another channel.
+    ///
+    /// # Example
+    /// ```
+    /// use lock_free_spsc::spsc::unbounded_spsc::UnboundSpscChannel;
+    ///
+    /// let (tx, rx) = UnboundSpscChannel::split();
+    /// (&tx).extend(0..1000);
+    /// assert_eq!(rx.recv(), Some(0));
+    ///
+    /// let undelivered = tx.reunite(rx).unwrap().into_vec();
+    /// assert!(undelivered.into_iter().eq(1..1000));
+    /// ```
+    pub fn reunite(self, rx: Receiver<T, O, A>) -> Result<Channel<T, O, A>, Mismatch<T, O, A>> {
+        if !Arc::ptr_eq(&self.inner, &rx.inner) {
+            return Err(ReuniteError(self, rx));
+        }
+        drop(rx.into_shared());
+        match Arc::into_inner(self.into_shared()) {
+            Some(shared) => Ok(Channel { shared }),
+            None => unreachable!("both halves of the channel were given up"),
+        }
+    }
+
+    /// Gives up the sender's share of the channel without closing it.
+    fn into_shared(self) -> Arc<Shared<T, O, A>> {
+        let this = ManuallyDrop::new(self);
+        // `this` is never used or dropped again, so the `Arc` moves out once.
+        unsafe { ptr::read(&this.inner) }
     }
 }
 
-impl<T> Receiver<T> {
+/// The error returned by [`Sender::reunite`].
+type Mismatch<T, O, A> = ReuniteError<Sender<T, O, A>, Receiver<T, O, A>>;
+
+impl<T, O: SpscObserver, A: SpscAlloc> Receiver<T, O, A> {
     /// Receives a value from the channel, or returns [`None`] if the channel is empty.
-    ///
-    /// # Safety
-    /// Only one receiver thread must call this method.
     #[inline]
     pub fn recv(&self) -> Option<T> {
-        self.inner.pop()
+        if let Some(after) = self.inner.shrink_after {
+            self.note_idle(after);
+        }
+        // The receiver is neither `Clone` nor `Sync`, so it is the only consumer.
+        let Some(value) = (unsafe { self.inner.queue.pop() }) else {
+            self.inner.consumer.empty();
+            self.inner.observer.on_empty_poll();
+            return None;
+        };
+        if self.inner.limit.max_weight.is_some() {
+            let weight = (self.inner.limit.weigh)(&value);
+            let received = self.inner.weight_received.load(Relaxed);
+            self.inner.weight_received.store(received.wrapping_add(weight), Release);
+        }
+        self.inner.consumer.received();
+        self.inner.observer.on_recv();
+        if self.inner.limit.blocking {
+            self.inner.tx_slot.notify();
+        }
+        Some(value)
+    }
+
+    /// Receives a value, parking the thread while the channel is empty.
+    ///
+    /// Returns `None` once the [`Sender`] has been dropped and every value it
+    /// sent has been received.
+    pub fn recv_blocking(&self) -> Option<T> {
+        let parked = |duration| self.inner.consumer.parked(duration);
+        let wait = self.inner.options.wait;
+        wait_with(wait, &[&self.inner.rx_slot], parked, || match self.recv() {
+            Some(value) => Some(Some(value)),
+            // The sender publishes everything before closing, so look once more.
+            None if self.is_disconnected() => Some(self.recv()),
+            None => None,
+        })
+    }
+
+    /// Returns an iterator over the values currently in the channel.
+    ///
+    /// The iterator stops as soon as the channel is empty.
+    pub fn try_iter(&self) -> TryIter<'_, T, O, A> {
+        TryIter { receiver: self }
+    }
+
+    /// Returns an iterator that blocks for each value and stops once the
+    /// [`Sender`] has been dropped and the channel is drained.
+    pub fn iter(&self) -> Iter<'_, T, O, A> {
+        Iter { receiver: self }
+    }
+
+    /// Returns `true` if the [`Sender`] has been dropped.
+    ///
+    /// Values sent before the disconnection may still be waiting in the channel.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.tx_closed.load(Acquire)
+    }
+
+    /// Returns how much of its [`Limit`] the channel is using.
+    pub fn usage(&self) -> Usage {
+        self.inner.usage()
+    }
+
+    /// Returns unused segments to the allocator: the spare segments kept for
+    /// reuse, and a drained segment the sender has already moved past.
+    ///
+    /// Segments still holding values are kept, so the channel never drops
+    /// below one segment.
+    pub fn shrink_to_fit(&self) {
+        // Called on the receiver, the only consumer.
+        unsafe { self.inner.queue.shrink() }
+    }
+
+    /// Counts a receive towards the idle-shrink policy, shrinking once the
+    /// queue has fit in one segment for long enough.
+    fn note_idle(&self, after: usize) {
+        // Called on the receiver, the only consumer.
+        if !unsafe { self.inner.queue.is_single_segment() } {
+            self.inner.idle_ops.store(0, Relaxed);
+            return;
+        }
+        let idle = self.inner.idle_ops.load(Relaxed) + 1;
+        if idle < after {
+            self.inner.idle_ops.store(idle, Relaxed);
+            return;
+        }
+        self.inner.idle_ops.store(0, Relaxed);
+        self.shrink_to_fit();
+    }
+
+    /// Returns the observer attached to the channel.
+    pub fn observer(&self) -> &O {
+        &self.inner.observer
+    }
+
+    /// Returns the name the channel was [built](crate::spsc::builder) with.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.options.name.as_deref()
+    }
+
+    /// Returns a snapshot of the channel's counters.
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> ChannelStats {
+        self.inner.stats()
+    }
+
+    /// Returns a reference to the next value without receiving it.
+    ///
+    /// Takes `&mut self` so the value cannot be received while borrowed.
+    #[inline]
+    pub fn peek(&mut self) -> Option<&T> {
+        // Only the receiver pops, and the exclusive borrow keeps it from doing so.
+        unsafe { self.inner.queue.peek() }
+    }
+
+    /// Returns the number of values currently in the channel.
+    ///
+    /// This walks the queue's segments, so it is linear in their number.
+    pub fn len(&self) -> usize {
+        // Called on the receiver, the only consumer.
+        unsafe { self.inner.queue.len() }
+    }
+
+    /// Returns `true` if the channel is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        // Called on the receiver, the only consumer.
+        unsafe { self.inner.queue.is_empty() }
+    }
+
+    /// Gives up the receiver's share of the channel without closing it.
+    fn into_shared(self) -> Arc<Shared<T, O, A>> {
+        let this = ManuallyDrop::new(self);
+        // `this` is never used or dropped again, so the `Arc` moves out once.
+        unsafe { ptr::read(&this.inner) }
+    }
+}
+
+/// An unbounded channel whose halves were put back together by
+/// [`Sender::reunite`].
+///
+/// It owns the values that were sent and never received: take them out with
+/// [`drain`](Self::drain) or [`into_vec`](Self::into_vec), to persist them
+/// during a shutdown for instance. Whatever is left is dropped with it.
+pub struct Channel<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    shared: Shared<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Channel<T, O, A> {
+    /// Returns an iterator that takes the values out of the channel, oldest
+    /// first. Values it does not reach stay in the channel.
+    pub fn drain(&mut self) -> Drain<'_, T, O, A> {
+        Drain { channel: self }
+    }
+
+    /// Takes every value out of the channel, oldest first.
+    pub fn into_vec(mut self) -> Vec<T> {
+        let mut values = Vec::with_capacity(self.len());
+        values.extend(self.drain());
+        values
+    }
+
+    /// Returns the number of values in the channel.
+    ///
+    /// This walks the queue's segments, so it is linear in their number.
+    pub fn len(&self) -> usize {
+        // The channel owns the queue outright, so it is the only consumer.
+        unsafe { self.shared.queue.len() }
+    }
+
+    /// Returns `true` if the channel holds no values.
+    pub fn is_empty(&self) -> bool {
+        // The channel owns the queue outright, so it is the only consumer.
+        unsafe { self.shared.queue.is_empty() }
+    }
+
+    /// Returns how much of its [`Limit`] the channel is using.
+    pub fn usage(&self) -> Usage {
+        self.shared.usage()
+    }
+
+    /// Returns the name the channel was [built](crate::spsc::builder) with.
+    pub fn name(&self) -> Option<&str> {
+        self.shared.options.name.as_deref()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> fmt::Debug for Channel<T, O, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("name", &self.name())
+            .field("usage", &self.usage())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator that takes the values out of a reunited unbounded channel.
+///
+/// Created by [`Channel::drain`].
+pub struct Drain<'a, T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    channel: &'a mut Channel<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for Drain<'_, T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let shared = &self.channel.shared;
+        // The channel owns the queue outright, so it is the only consumer.
+        let value = unsafe { shared.queue.pop() }?;
+        // Keeps `usage` in step with what is left.
+        if shared.limit.max_weight.is_some() {
+            let weight = (shared.limit.weigh)(&value);
+            let received = shared.weight_received.load(Relaxed);
+            shared.weight_received.store(received.wrapping_add(weight), Relaxed);
+        }
+        Some(value)
     }
 }
 
 impl UnboundSpscChannel {
     /// Creates a new unbounded SPSC channel.
     ///
-    /// Returns a tuple of `(Sender<T>, Receiver<T>)`, which represent the only producer
+    /// Returns a tuple of `(Sender<T, O, A>, Receiver<T, O, A>)`, which represent the only producer
     /// and consumer endpoints respectively.
     ///
     /// Internally, the shared `RawSpsc<T>` queue is wrapped in an [`Arc`] and passed to both ends.
@@ -68,21 +564,269 @@ impl UnboundSpscChannel {
     /// # Panics
     /// Panics if the underlying queue allocation fails.
     pub fn split<T>() -> (Sender<T>, Receiver<T>) {
-        let inner = Arc::new(RawSpsc::new());
-        (Sender { inner: inner.clone(), _no_clone: NoClone }, Receiver { inner,_no_clone: NoClone })
+        Self::split_with_observer(NoopObserver)
+    }
+
+    /// Like [`split`](Self::split), but returns a [`ChannelError`] if the
+    /// first segment cannot be allocated instead of panicking.
+    pub fn try_split<T>() -> Result<Halves<T, NoopObserver, Global>, ChannelError> {
+        Self::try_split_in(Global)
+    }
+
+    /// Creates an unbounded channel that keeps up to `max_spare` drained
+    /// segments for reuse instead of freeing them.
+    ///
+    /// [`split`](Self::split) keeps [`DEFAULT_SPARE_SEGMENTS`]. A larger pool
+    /// lets a channel absorb repeated bursts of that many segments without
+    /// allocating; `0` frees every drained segment straight away.
+    ///
+    /// [`DEFAULT_SPARE_SEGMENTS`]: super::DEFAULT_SPARE_SEGMENTS
+    pub fn split_with_spares<T>(max_spare: usize) -> (Sender<T>, Receiver<T>) {
+        Self::from_queue(
+            RawSpsc::with_spare_segments(max_spare),
+            Limit::default(),
+            None,
+            NoopObserver,
+            Options::default(),
+        )
+    }
+
+    /// Creates an unbounded channel whose segments are sized by `size`.
+    ///
+    /// The default is 128 slots per segment whatever `T` is; size segments
+    /// by bytes for large or tiny values, and let them grow for producers
+    /// that burst far ahead of the consumer. See [`SegmentSize`].
+    ///
+    /// ```
+    /// use lock_free_spsc::spsc::unbounded_spsc::{SegmentSize, UnboundSpscChannel};
+    ///
+    /// let size = SegmentSize::bytes::<[u8; 4096]>(64 * 1024).growing_to(1024);
+    /// let (tx, rx) = UnboundSpscChannel::split_with_segment_size(size);
+    /// tx.send([0u8; 4096]).unwrap();
+    /// assert_eq!(rx.recv().map(|page| page.len()), Some(4096));
+    /// ```
+    pub fn split_with_segment_size<T>(size: SegmentSize) -> (Sender<T>, Receiver<T>) {
+        Self::from_queue(
+            RawSpsc::with_options(size, DEFAULT_SPARE_SEGMENTS),
+            Limit::default(),
+            None,
+            NoopObserver,
+            Options::default(),
+        )
+    }
+
+    /// Creates an unbounded channel that holds no more than `limit` allows.
+    ///
+    /// Both halves report the current [`Usage`]. See [`Limit`].
+    pub fn with_limit<T>(limit: Limit<T>) -> (Sender<T>, Receiver<T>) {
+        Self::from_queue(RawSpsc::new(), limit, None, NoopObserver, Options::default())
+    }
+
+    /// Creates an unbounded channel that shrinks itself once it has been
+    /// empty, or small enough to fit in one segment, for `idle_ops` receives
+    /// in a row.
+    ///
+    /// Shrinking frees what [`Receiver::shrink_to_fit`] frees, so memory
+    /// taken by a burst is given back once the burst has been received.
+    /// Sizing `idle_ops` well above the length of a typical lull keeps the
+    /// spare segments around between bursts.
+    pub fn split_with_idle_shrink<T>(idle_ops: usize) -> (Sender<T>, Receiver<T>) {
+        Self::from_queue(
+            RawSpsc::new(),
+            Limit::default(),
+            Some(idle_ops.max(1)),
+            NoopObserver,
+            Options::default(),
+        )
+    }
+
+    /// Creates an unbounded channel that reports its events to `observer`.
+    ///
+    /// See [`observer`](crate::spsc::observer) for the available hooks.
+    pub fn split_with_observer<T, O: SpscObserver>(observer: O) -> (Sender<T, O>, Receiver<T, O>) {
+        Self::from_queue(RawSpsc::new(), Limit::default(), None, observer, Options::default())
+    }
+
+    /// Creates an unbounded channel whose segments are allocated from `alloc`.
+    ///
+    /// See [`alloc`](crate::spsc::alloc) for the allocator interface.
+    pub fn split_in<T, A: SpscAlloc>(
+        alloc: A,
+    ) -> (Sender<T, NoopObserver, A>, Receiver<T, NoopObserver, A>) {
+        let queue = RawSpsc::with_options_in(SegmentSize::default(), DEFAULT_SPARE_SEGMENTS, alloc);
+        Self::from_queue(queue, Limit::default(), None, NoopObserver, Options::default())
+    }
+
+    /// Like [`split_in`](Self::split_in), but returns a [`ChannelError`] if
+    /// `alloc` cannot provide the first segment instead of panicking.
+    ///
+    /// Later segments are allocated as the channel grows; see
+    /// [`Sender::try_send`] for sending without panicking when that fails.
+    pub fn try_split_in<T, A: SpscAlloc>(
+        alloc: A,
+    ) -> Result<Halves<T, NoopObserver, A>, ChannelError> {
+        let queue =
+            RawSpsc::try_with_options_in(SegmentSize::default(), DEFAULT_SPARE_SEGMENTS, alloc)?;
+        Ok(Self::from_queue(queue, Limit::default(), None, NoopObserver, Options::default()))
+    }
+
+    pub(crate) fn from_queue<T, O: SpscObserver, A: SpscAlloc>(
+        queue: RawSpsc<T, A>,
+        limit: Limit<T>,
+        shrink_after: Option<usize>,
+        observer: O,
+        options: Options,
+    ) -> (Sender<T, O, A>, Receiver<T, O, A>) {
+        let inner = Arc::new(Shared {
+            queue,
+            limit,
+            shrink_after,
+            idle_ops: AtomicUsize::new(0),
+            weight_sent: CachePadded::new(AtomicUsize::new(0)),
+            weight_received: CachePadded::new(AtomicUsize::new(0)),
+            rx_slot: WaitSlot::new(),
+            tx_slot: WaitSlot::new(),
+            tx_closed: AtomicBool::new(false),
+            rx_closed: AtomicBool::new(false),
+            producer: ProducerCounters::new(options.metrics),
+            consumer: ConsumerCounters::new(options.metrics),
+            observer,
+            options,
+        });
+        (
+            Sender { inner: inner.clone(), _no_clone: NoClone, _not_sync: PhantomData },
+            Receiver { inner, _no_clone: NoClone, _not_sync: PhantomData },
+        )
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> fmt::Debug for Sender<T, O, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("name", &self.name())
+            .field("usage", &self.usage())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> fmt::Debug for Receiver<T, O, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("name", &self.name())
+            .field("usage", &self.usage())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Drop for Sender<T, O, A> {
+    fn drop(&mut self) {
+        self.inner.tx_closed.store(true, Release);
+        self.inner.rx_slot.notify();
+        self.inner.observer.on_disconnect(Side::Sender);
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Drop for Receiver<T, O, A> {
+    fn drop(&mut self) {
+        self.inner.rx_closed.store(true, Release);
+        self.inner.tx_slot.notify();
+        self.inner.observer.on_disconnect(Side::Receiver);
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Extend<T> for &Sender<T, O, A> {
+    /// Sends every item with [`Sender::send`].
+    ///
+    /// Stops early if the channel's [`Limit`] refuses an item; that item is
+    /// dropped and the rest of the iterator is left untouched.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            if self.send(value).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// An iterator over the values currently in an unbounded channel.
+///
+/// Created by [`Receiver::try_iter`].
+pub struct TryIter<'a, T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    receiver: &'a Receiver<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for TryIter<'_, T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv()
+    }
+}
+
+/// A blocking iterator over the values of an unbounded channel.
+///
+/// Created by [`Receiver::iter`].
+pub struct Iter<'a, T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    receiver: &'a Receiver<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for Iter<'_, T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv_blocking()
+    }
+}
+
+/// An owning blocking iterator over the values of an unbounded channel.
+///
+/// Created by the [`IntoIterator`] implementation of [`Receiver`].
+pub struct IntoIter<T, O: SpscObserver = NoopObserver, A: SpscAlloc = Global> {
+    receiver: Receiver<T, O, A>,
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> Iterator for IntoIter<T, O, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv_blocking()
     }
 }
 
-unsafe impl<T> Send for Sender<T> {}
-unsafe impl<T> Sync for Sender<T> {}
-unsafe impl<T> Send for Receiver<T> {}
-unsafe impl<T> Sync for Receiver<T> {}
+impl<'a, T, O: SpscObserver, A: SpscAlloc> IntoIterator for &'a Receiver<T, O, A> {
+    type Item = T;
+    type IntoIter = Iter<'a, T, O, A>;
+
+    fn into_iter(self) -> Iter<'a, T, O, A> {
+        self.iter()
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> IntoIterator for Receiver<T, O, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, O, A>;
+
+    fn into_iter(self) -> IntoIter<T, O, A> {
+        IntoIter { receiver: self }
+    }
+}
+
+impl<T, O: SpscObserver, A: SpscAlloc> sealed::Selectable for Receiver<T, O, A> {
+    fn is_ready(&self) -> bool {
+        // Only receivers are selectable.
+        !unsafe { self.inner.queue.is_empty() } || self.is_disconnected()
+    }
+
+    fn rx_slot(&self) -> &WaitSlot {
+        &self.inner.rx_slot
+    }
+}
 
 
 #[cfg(test)]
 mod tests {
     use std::thread;
-    use super::UnboundSpscChannel;
+    use super::{Limit, LimitExceeded, ReuniteError, UnboundSpscChannel};
 
     const COUNT: usize = 100_000;
 
@@ -91,7 +835,7 @@ mod tests {
         let (sender, receiver) = UnboundSpscChannel::split();
 
         for i in 0..1000 {
-            sender.send(i);
+            sender.send(i).unwrap();
             let val = receiver.recv();
             assert_eq!(val, Some(i));
         }
@@ -104,7 +848,7 @@ mod tests {
         let (sender, receiver) = UnboundSpscChannel::split();
 
         for i in 0..COUNT {
-            sender.send(i);
+            sender.send(i).unwrap();
         }
 
         for i in 0..COUNT {
@@ -121,7 +865,7 @@ mod tests {
 
         let producer = thread::spawn(move || {
             for i in 0..COUNT {
-                sender.send(i);
+                sender.send(i).unwrap();
             }
         });
 
@@ -141,4 +885,169 @@ mod tests {
         producer.join().unwrap();
         consumer.join().unwrap();
     }
+
+    #[test]
+    fn try_iter_drains_until_empty() {
+        let (sender, receiver) = UnboundSpscChannel::split();
+        (&sender).extend(0..300);
+        assert!(receiver.try_iter().eq(0..300));
+        assert_eq!(receiver.recv(), None);
+        drop(sender);
+        assert!(receiver.is_disconnected());
+    }
+
+    #[test]
+    fn iter_ends_on_disconnect() {
+        let (sender, receiver) = UnboundSpscChannel::split();
+        let producer = thread::spawn(move || {
+            (&sender).extend(0..COUNT);
+        });
+        assert!(receiver.iter().eq(0..COUNT));
+        assert_eq!(receiver.recv_blocking(), None);
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn segment_limit_refuses_sends() {
+        let (sender, receiver) = UnboundSpscChannel::with_limit(Limit::segments(2));
+        let mut sent = 0;
+        while sender.send(sent).is_ok() {
+            sent += 1;
+        }
+        // Two segments of 128 slots, one of each kept free.
+        assert_eq!(sent, 254);
+        assert_eq!(sender.send(sent), Err(LimitExceeded(sent)));
+        assert_eq!(receiver.usage().segments, 2);
+
+        // Draining the first segment turns it into a spare, which makes room.
+        assert!(receiver.try_iter().take(128).eq(0..128));
+        assert_eq!(sender.send(sent), Ok(()));
+        assert_eq!(sender.usage().segments, 2);
+    }
+
+    #[test]
+    fn weight_limit_refuses_sends() {
+        let (sender, receiver) = UnboundSpscChannel::with_limit(Limit::weight(10));
+        sender.send(String::from("abcdef")).unwrap();
+        assert_eq!(sender.send(String::from("ghijk")).unwrap_err().into_inner(), "ghijk");
+        assert_eq!(sender.usage().weight, 6);
+        assert_eq!(receiver.usage().weight, 6);
+
+        sender.send(String::from("ghij")).unwrap();
+        assert_eq!(receiver.usage().weight, 10);
+        assert_eq!(receiver.recv().as_deref(), Some("abcdef"));
+        assert_eq!(sender.usage().weight, 4);
+        sender.send(String::from("klmnop")).unwrap();
+        assert!(sender.send(String::from("too heavy to fit")).is_err());
+    }
+
+    #[test]
+    fn blocking_limit_waits_for_room() {
+        let limit = Limit::weight(5).blocking();
+        let (sender, receiver) = UnboundSpscChannel::with_limit(limit);
+        let producer = thread::spawn(move || {
+            for word in ["one", "two", "three"] {
+                sender.send(String::from(word)).unwrap();
+            }
+            // Never fits, so it fails rather than blocking forever.
+            sender.send(String::from("eleven")).unwrap_err();
+            sender.send(String::from("four")).unwrap();
+            // Only fits once "four" is received, which never happens.
+            sender.send(String::from("five")).unwrap_err()
+        });
+        assert!(receiver.iter().take(3).eq(["one", "two", "three"]));
+        drop(receiver);
+        assert_eq!(producer.join().unwrap().into_inner(), "five");
+    }
+
+    #[test]
+    fn idle_shrink_returns_burst_memory() {
+        let (sender, receiver) = UnboundSpscChannel::split_with_idle_shrink(200);
+        let idle = receiver.usage();
+        assert_eq!(idle.segments, 1);
+
+        (&sender).extend(0..1000);
+        assert!(receiver.usage().bytes > idle.bytes);
+        assert!(receiver.try_iter().eq(0..1000));
+        // A drained segment waits in the spare pool, and the last one still
+        // had more than a hundred values to receive.
+        assert_eq!(sender.usage().segments, 2);
+        for _ in 0..100 {
+            assert_eq!(receiver.recv(), None);
+        }
+        assert_eq!(sender.usage(), idle);
+    }
+
+    #[test]
+    fn shrink_to_fit_keeps_queued_values() {
+        let (sender, receiver) = UnboundSpscChannel::split_with_spares(8);
+        (&sender).extend(0..1000);
+        assert!(receiver.try_iter().take(900).eq(0..900));
+        let burst = receiver.usage();
+        receiver.shrink_to_fit();
+        let shrunk = receiver.usage();
+        assert!(shrunk.segments < burst.segments);
+        assert!(shrunk.bytes < burst.bytes);
+        assert!(receiver.try_iter().eq(900..1000));
+        receiver.shrink_to_fit();
+        assert_eq!(receiver.usage().segments, 1);
+    }
+
+    // Zero-sized values are only counted; these also run under Miri in CI.
+    #[test]
+    fn zst_signals() {
+        let (sender, mut receiver) = UnboundSpscChannel::split::<()>();
+        // Well past a segment's worth, without linking a second one.
+        (0..1000).for_each(|_| sender.send(()).unwrap());
+        assert_eq!(receiver.len(), 1000);
+        assert_eq!(receiver.peek(), Some(&()));
+        assert_eq!(receiver.try_iter().count(), 1000);
+        assert!(receiver.is_empty());
+        assert_eq!(receiver.peek(), None);
+        #[cfg(feature = "metrics")]
+        assert_eq!(receiver.stats().segments_allocated, 1);
+    }
+
+    #[test]
+    fn zst_values_dropped_once() {
+        use std::cell::Cell;
+
+        thread_local!(static DROPS: Cell<usize> = const { Cell::new(0) });
+        struct Token;
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPS.set(DROPS.get() + 1);
+            }
+        }
+
+        let (sender, receiver) = UnboundSpscChannel::split();
+        (0..300).for_each(|_| sender.send(Token).unwrap());
+        drop(receiver.recv());
+        assert_eq!(DROPS.get(), 1);
+        drop((sender, receiver));
+        assert_eq!(DROPS.get(), 300);
+    }
+
+    #[test]
+    fn reunite_recovers_undelivered_values() {
+        let (sender, receiver) = UnboundSpscChannel::with_limit(Limit::weight(64));
+        (&sender).extend((0..10).map(|i| i.to_string()));
+        assert_eq!(receiver.recv().as_deref(), Some("0"));
+
+        let mut channel = sender.reunite(receiver).unwrap();
+        assert_eq!(channel.len(), 9);
+        assert!(channel.drain().take(2).eq(["1", "2"]));
+        assert_eq!(channel.usage().weight, 7);
+        assert!(channel.into_vec().into_iter().eq((3..10).map(|i| i.to_string())));
+    }
+
+    #[test]
+    fn reunite_refuses_foreign_receiver() {
+        let (sender, _receiver) = UnboundSpscChannel::split();
+        let (_other, foreign) = UnboundSpscChannel::split();
+        let ReuniteError(sender, foreign) = sender.reunite(foreign).unwrap_err();
+        // Both halves come back still connected.
+        sender.send(1).unwrap();
+        assert!(!sender.is_disconnected() && !foreign.is_disconnected());
+    }
 }
@@ -0,0 +1,175 @@
+//! Caps on how much an unbounded channel may hold.
+//!
+//! An unbounded channel grows for as long as the producer outruns the
+//! consumer. A [`Limit`] bounds that growth, either by the number of
+//! segments the queue holds or by the total [`Weigh`]t of the values in it,
+//! so a stalled consumer cannot exhaust memory:
+//!
+//! ```
+//! use lock_free_spsc::spsc::unbounded_spsc::{Limit, UnboundSpscChannel};
+//!
+//! let (tx, rx) = UnboundSpscChannel::with_limit(Limit::weight(8));
+//! tx.send(String::from("hello")).unwrap();
+//! let rejected = tx.send(String::from("world")).unwrap_err();
+//! assert_eq!(rejected.into_inner(), "world");
+//! assert_eq!(rx.usage().weight, 5);
+//! ```
+//!
+//! Once the limit is reached, [`Sender::send`](super::Sender::send) hands the
+//! value back in a [`LimitExceeded`], or parks until the consumer makes room
+//! if the limit is [`blocking`](Limit::blocking).
+
+use std::error::Error;
+use std::fmt;
+
+/// A measure of how much of a channel's [`Limit`] a value uses up.
+///
+/// Usually the number of bytes the value owns, but any unit works as long as
+/// the limit is given in the same one. The weight of a value must not change
+/// while it is in the channel.
+pub trait Weigh {
+    /// Returns the weight of this value.
+    fn weigh(&self) -> usize;
+}
+
+impl Weigh for String {
+    fn weigh(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Weigh for Vec<T> {
+    fn weigh(&self) -> usize {
+        self.len() * size_of::<T>()
+    }
+}
+
+impl<T> Weigh for Box<[T]> {
+    fn weigh(&self) -> usize {
+        self.len() * size_of::<T>()
+    }
+}
+
+/// How much an unbounded channel may hold before sends are refused.
+///
+/// Created with [`segments`](Self::segments) or [`weight`](Self::weight),
+/// and passed to [`UnboundSpscChannel::with_limit`]. The default is no limit.
+///
+/// [`UnboundSpscChannel::with_limit`]: super::UnboundSpscChannel::with_limit
+pub struct Limit<T> {
+    pub(crate) max_segments: Option<usize>,
+    pub(crate) max_weight: Option<usize>,
+    /// Weighs each value; returns `0` without a weight limit.
+    pub(crate) weigh: fn(&T) -> usize,
+    pub(crate) blocking: bool,
+}
+
+impl<T> Limit<T> {
+    /// Limits the channel to `max` segments, counting spare segments kept
+    /// for reuse.
+    ///
+    /// The first segment is always held, so `0` behaves like `1`.
+    pub fn segments(max: usize) -> Self {
+        Limit {
+            max_segments: Some(max.max(1)),
+            ..Self::default()
+        }
+    }
+
+    /// Limits the total [`Weigh::weigh`] of the values in the channel to `max`.
+    ///
+    /// A value heavier than `max` on its own is always refused.
+    pub fn weight(max: usize) -> Self
+    where
+        T: Weigh,
+    {
+        Limit {
+            max_weight: Some(max),
+            weigh: T::weigh,
+            ..Self::default()
+        }
+    }
+
+    /// Makes `send` park until the consumer makes room, rather than refusing
+    /// the value.
+    ///
+    /// A blocked send still fails if the receiver is dropped, or if the value
+    /// is too heavy to ever fit.
+    pub fn blocking(self) -> Self {
+        Limit { blocking: true, ..self }
+    }
+
+    /// Returns the segment limit, if any.
+    pub fn max_segments(&self) -> Option<usize> {
+        self.max_segments
+    }
+
+    /// Returns the weight limit, if any.
+    pub fn max_weight(&self) -> Option<usize> {
+        self.max_weight
+    }
+
+    /// Returns `true` if sends park instead of failing at the limit.
+    pub fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+}
+
+impl<T> Default for Limit<T> {
+    fn default() -> Self {
+        Limit {
+            max_segments: None,
+            max_weight: None,
+            weigh: |_| 0,
+            blocking: false,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Limit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Limit")
+            .field("max_segments", &self.max_segments)
+            .field("max_weight", &self.max_weight)
+            .field("blocking", &self.blocking)
+            .finish()
+    }
+}
+
+/// How much of its [`Limit`] a channel is using, as seen from either half.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    /// Segments held, including spares kept for reuse.
+    pub segments: usize,
+    /// Bytes held by those segments, buffers and headers together.
+    pub bytes: usize,
+    /// Total weight of the values in the channel; always `0` without a
+    /// weight limit.
+    pub weight: usize,
+}
+
+/// The error returned by [`Sender::send`](super::Sender::send) when the
+/// channel's [`Limit`] is reached, holding the value that was not sent.
+#[derive(PartialEq, Eq)]
+pub struct LimitExceeded<T>(pub T);
+
+impl<T> LimitExceeded<T> {
+    /// Returns the value that was not sent.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for LimitExceeded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LimitExceeded(..)")
+    }
+}
+
+impl<T> fmt::Display for LimitExceeded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sending on a channel that reached its limit")
+    }
+}
+
+impl<T> Error for LimitExceeded<T> {}
@@ -1,4 +1,9 @@
-mod raw_spsc;
+pub(crate) mod raw_spsc;
 mod channel;
+mod limit;
 
-pub use channel::{UnboundSpscChannel,Sender, Receiver};
\ No newline at end of file
+pub use channel::{
+    Channel, Drain, IntoIter, Iter, Receiver, Sender, TryIter, UnboundSpscChannel,
+};
+pub use limit::{Limit, LimitExceeded, Usage, Weigh};
+pub use raw_spsc::{DEFAULT_SPARE_SEGMENTS, SegmentSize};
@@ -1,15 +1,122 @@
 use crate::cache_padded::CachePadded;
-use std::alloc::{Layout, alloc, dealloc};
-use std::mem::MaybeUninit;
-use std::ptr::{NonNull, null_mut};
-use std::sync::atomic::AtomicUsize;
-use std::sync::atomic::{
-    AtomicPtr,
-    Ordering::{Acquire, Relaxed, Release},
-};
+use crate::spsc::drop_guard::{OnDrop, for_each_unwinding};
+use crate::spsc::alloc::{Global, SpscAlloc};
+use crate::spsc::error::ChannelError;
+use std::alloc::Layout;
+use crate::sync::{AtomicPtr, AtomicUsize, UnsafeCell};
+use std::mem::{self, MaybeUninit};
+use std::ptr::{self, NonNull, null_mut};
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
 
+/// Default segment length, in slots.
+#[cfg(not(loom))]
 const SEGMENT_SIZE: usize = 128;
-const MASK: usize = 0x7F;
+// Small segments keep loom's state space tractable while still crossing
+// segment boundaries after a handful of pushes.
+#[cfg(loom)]
+const SEGMENT_SIZE: usize = 4;
+
+/// How many drained segments a queue keeps for reuse unless told otherwise.
+pub const DEFAULT_SPARE_SEGMENTS: usize = 1;
+
+/// How long the unbounded queue's segments are, and whether they grow.
+///
+/// Lengths are counted in slots and always a power of two, at least 2. A
+/// segment holds one value less than its length, since one slot stays empty
+/// to tell a full segment from an empty one.
+///
+/// # Example
+/// ```
+/// use lock_free_spsc::spsc::unbounded_spsc::SegmentSize;
+///
+/// // About 64 KiB per segment of 4 KiB messages.
+/// let size = SegmentSize::bytes::<[u8; 4096]>(64 * 1024);
+/// assert_eq!(size.initial_slots(), 16);
+///
+/// // 256-slot segments, doubling up to 4096 slots while the consumer lags.
+/// let size = SegmentSize::slots(200).growing_to(4096);
+/// assert_eq!((size.initial_slots(), size.max_slots()), (256, 4096));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentSize {
+    slots: usize,
+    max_slots: usize,
+}
+
+impl SegmentSize {
+    /// Segments of `slots` slots, rounded up to a power of two.
+    ///
+    /// # Panics
+    /// Panics if the rounded length overflows `usize`.
+    pub const fn slots(slots: usize) -> Self {
+        let slots = Self::round(slots);
+        SegmentSize {
+            slots,
+            max_slots: slots,
+        }
+    }
+
+    /// Segments of at most `bytes` bytes for values of type `T`: the largest
+    /// power of two of slots that fits, but at least 2.
+    ///
+    /// Zero-sized values take no space, so they get the default length.
+    pub const fn bytes<T>(bytes: usize) -> Self {
+        let size = mem::size_of::<T>();
+        if size == 0 {
+            return Self::slots(SEGMENT_SIZE);
+        }
+        let fits = bytes / size;
+        if fits < 2 {
+            return Self::slots(2);
+        }
+        // Round down to a power of two.
+        Self::slots(1 << (usize::BITS - 1 - fits.leading_zeros()))
+    }
+
+    /// Lets the segment length double, up to `max_slots` rounded up to a
+    /// power of two, whenever the producer needs a new segment while the
+    /// consumer is still at least a whole segment behind.
+    ///
+    /// Segments never shrink back; drained ones shorter than the current
+    /// length are freed rather than reused.
+    ///
+    /// # Panics
+    /// Panics if the rounded length overflows `usize`.
+    pub const fn growing_to(self, max_slots: usize) -> Self {
+        let max_slots = Self::round(max_slots);
+        SegmentSize {
+            slots: self.slots,
+            max_slots: if max_slots > self.slots { max_slots } else { self.slots },
+        }
+    }
+
+    /// Returns the length of the first segment, in slots.
+    pub const fn initial_slots(&self) -> usize {
+        self.slots
+    }
+
+    /// Returns the length segments may grow to, in slots.
+    pub const fn max_slots(&self) -> usize {
+        self.max_slots
+    }
+
+    const fn round(slots: usize) -> usize {
+        match slots.checked_next_power_of_two() {
+            Some(slots) if slots >= 2 => slots,
+            Some(_) => 2,
+            None => panic!("segment length overflows usize"),
+        }
+    }
+}
+
+impl Default for SegmentSize {
+    /// Fixed segments of 128 slots.
+    fn default() -> Self {
+        Self::slots(SEGMENT_SIZE)
+    }
+}
+
+type Slot<T> = UnsafeCell<MaybeUninit<T>>;
 
 /// A lock-free single-producer single-consumer (SPSC) queue implemented as a linked list of fixed-size segments.
 ///
@@ -27,92 +134,490 @@ const MASK: usize = 0x7F;
 /// Thus, the queue behaves like a FIFO queue backed by a segmented ring buffer that grows only when necessary.
 ///
 /// # Memory Management
-/// Each segment allocates a contiguous block of uninitialized memory for `SEGMENT_SIZE` elements of type `T`.
+/// Each segment allocates a contiguous block of uninitialized memory for a power-of-two number of
+/// elements of type `T`, chosen by [`SegmentSize`]. With a growth limit, each new segment doubles
+/// the previous one while the consumer is more than a segment behind, up to that limit.
 /// Elements are pushed and popped using atomic operations on head and tail indices within the segment.
-/// When a segment becomes empty and is no longer needed, it is dropped and its memory deallocated.
+/// When a segment becomes empty and is no longer needed, the consumer resets it and parks it in a
+/// small pool of spare segments, which the producer draws from before allocating a new one. Only
+/// once the pool is full is a drained segment dropped and its memory deallocated, so a queue whose
+/// length keeps crossing the same segment boundary stops allocating after warming up.
 ///
 /// # Safety
 /// Unsafe code is used internally to manage manual allocation and deallocation of memory and to read/write uninitialized memory.
-/// The queue assumes single-producer and single-consumer threads only.
+/// The queue assumes single-producer and single-consumer threads only. Nothing here enforces
+/// that, so the producer and consumer entry points are `unsafe fn`s that spell out which side
+/// may call them; the channel handles uphold it by being `!Sync` and not `Clone`.
 ///
 /// # Usage
 /// This low-level `RawSpsc` queue is intended to be wrapped by the higher-level [`UnboundSpsc`] abstraction,
 /// which provides a more user-friendly interface and additional functionality.
-pub struct RawSpsc<T> {
+pub struct RawSpsc<T, A: SpscAlloc = Global> {
     head: CachePadded<AtomicPtr<Segment<T>>>,
     tail: CachePadded<AtomicPtr<Segment<T>>>,
+    /// Drained segments waiting to be reused. Only the consumer fills an
+    /// empty slot, but both sides empty full ones: the producer to reuse a
+    /// spare and the consumer to shrink the pool, so slots are emptied with
+    /// a swap.
+    spares: Box<[AtomicPtr<Segment<T>>]>,
+    /// Segments allocated so far, written only by the producer.
+    allocated: CachePadded<AtomicUsize>,
+    /// Spare segments reused so far, written only by the producer.
+    recycled: CachePadded<AtomicUsize>,
+    /// Segments freed so far. Mostly written by the consumer, but the
+    /// producer discards spares that are too short after growing, so both
+    /// sides add to it.
+    freed: CachePadded<AtomicUsize>,
+    /// Bytes held by segments, linked or spare, written by both sides.
+    bytes: CachePadded<AtomicUsize>,
+    /// The longest segment the producer may link, in slots.
+    max_slots: usize,
+    /// Where segments come from and go back to.
+    alloc: A,
 }
 
 impl<T> RawSpsc<T> {
-    /// Creates a new `RawSpsc` queue with a single allocated segment.
+    /// Creates a new `RawSpsc` queue with a single allocated segment, keeping
+    /// up to [`DEFAULT_SPARE_SEGMENTS`] drained segments for reuse.
     pub fn new() -> Self {
-        let segment = Box::new(Segment::new());
-        let segment_ptr = Box::into_raw(segment);
+        Self::with_spare_segments(DEFAULT_SPARE_SEGMENTS)
+    }
+
+    /// Creates a new `RawSpsc` queue that keeps up to `max_spare` drained
+    /// segments for reuse instead of freeing them.
+    ///
+    /// With `0`, every drained segment is freed and every new one allocated.
+    pub fn with_spare_segments(max_spare: usize) -> Self {
+        Self::with_options(SegmentSize::default(), max_spare)
+    }
+
+    /// Creates a new `RawSpsc` queue with segments sized by `size`, keeping
+    /// up to `max_spare` drained segments for reuse.
+    pub fn with_options(size: SegmentSize, max_spare: usize) -> Self {
+        Self::with_options_in(size, max_spare, Global)
+    }
+}
+
+impl<T, A: SpscAlloc> RawSpsc<T, A> {
+    /// Zero-sized values carry no data, so the queue only counts them: they
+    /// all go to the first segment, whose indices then run freely instead of
+    /// wrapping at the segment length, and no further segment is ever allocated.
+    const IS_ZST: bool = mem::size_of::<T>() == 0;
+
+    /// Creates a new `RawSpsc` queue like [`with_options`](RawSpsc::with_options),
+    /// allocating its segments from `alloc`.
+    ///
+    /// # Panics
+    /// Panics if the first segment cannot be allocated.
+    pub fn with_options_in(size: SegmentSize, max_spare: usize, alloc: A) -> Self {
+        Self::try_with_options_in(size, max_spare, alloc).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`with_options_in`](Self::with_options_in), but returns an error
+    /// if the first segment cannot be allocated.
+    pub fn try_with_options_in(
+        size: SegmentSize,
+        max_spare: usize,
+        alloc: A,
+    ) -> Result<Self, ChannelError> {
+        let segment_ptr = Segment::try_new_in(size.slots, &alloc)?;
         let head = CachePadded::new(AtomicPtr::new(segment_ptr));
         let tail = CachePadded::new(AtomicPtr::new(segment_ptr));
 
-        RawSpsc { head, tail }
+        Ok(RawSpsc {
+            head,
+            tail,
+            spares: (0..max_spare).map(|_| AtomicPtr::new(null_mut())).collect(),
+            allocated: CachePadded::new(AtomicUsize::new(1)),
+            recycled: CachePadded::new(AtomicUsize::new(0)),
+            freed: CachePadded::new(AtomicUsize::new(0)),
+            bytes: CachePadded::new(AtomicUsize::new(Segment::<T>::footprint(size.slots))),
+            max_slots: size.max_slots,
+            alloc,
+        })
     }
 
     /// Attempts to push a value into the queue.
     ///
-    /// If the current tail segment is full, a new segment is allocated and linked.
-    pub fn push(&self, value: T) {
+    /// If the current tail segment is full, a spare segment is linked, or a
+    /// new one allocated if there is no spare. Returns `true` if a segment
+    /// had to be allocated, or hands the value back with the error if that
+    /// allocation failed, leaving the queue as it was.
+    ///
+    /// # Safety
+    /// Only one thread at a time may act as the producer: `try_push` must
+    /// never run concurrently with another `try_push` on the same queue.
+    pub unsafe fn try_push(&self, value: T) -> Result<bool, (T, ChannelError)> {
         let tail = self.tail.load(Acquire);
         let segment = unsafe { &*tail };
+        if Self::IS_ZST {
+            let pushed = segment.next_head.load(Relaxed);
+            assert_ne!(
+                pushed.wrapping_add(1),
+                segment.tail.load(Acquire),
+                "too many zero-sized values queued"
+            );
+            // Nothing to store: `pop` conjures the value back.
+            mem::forget(value);
+            segment.next_head.store(pushed.wrapping_add(1), Release);
+            return Ok(false);
+        }
         match unsafe { segment.push(value) } {
-            Ok(()) => return,
+            Ok(()) => Ok(false),
             Err(val) => {
-                let new_block_ptr = unsafe { segment.link_and_push(val) };
+                let slots = self.next_segment_slots(segment);
+                let (next, counter, fresh) = match self.take_spare(slots) {
+                    Some(spare) => (spare, &self.recycled, false),
+                    None => match self.alloc_segment(slots) {
+                        Ok(next) => (next, &self.allocated, true),
+                        Err(err) => return Err((val, err)),
+                    },
+                };
+                let new_block_ptr = unsafe { segment.link_and_push(next, val) };
                 self.tail.store(new_block_ptr, Release);
+                counter.store(counter.load(Relaxed) + 1, Relaxed);
+                Ok(fresh)
             }
         }
     }
 
+    /// Like [`try_push`](Self::try_push), panicking if a segment cannot be
+    /// allocated. For the tests and the differential harness.
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub unsafe fn push(&self, value: T) -> bool {
+        match unsafe { self.try_push(value) } {
+            Ok(fresh) => fresh,
+            Err((_, err)) => panic!("{err}"),
+        }
+    }
+
     /// Attempts to pop a value from the queue.
     ///
-    /// If the current head segment is empty but there is a next linked segment,
-    /// it advances to the next segment and pops from there.
-    pub fn pop(&self) -> Option<T> {
-        let head = self.head.load(Acquire);
-        let curr_head = unsafe { &*head };
-        let res = unsafe { curr_head.pop() };
-
-        match res {
+    /// If the current head segment is empty and the producer has moved on to a
+    /// new segment, it advances to that segment and pops from there.
+    ///
+    /// # Safety
+    /// Only one thread at a time may act as the consumer: `pop` must never
+    /// run concurrently with any other consumer method on the same queue,
+    /// since the consumer frees drained segments.
+    pub unsafe fn pop(&self) -> Option<T> {
+        // Only the consumer writes `head`.
+        let head = self.head.load(Relaxed);
+        if Self::IS_ZST {
+            let segment = unsafe { &*head };
+            let popped = segment.tail.load(Relaxed);
+            if segment.next_head.load(Acquire) == popped {
+                return None; // Queue is empty
+            }
+            segment.tail.store(popped.wrapping_add(1), Release);
+            return Some(unsafe { conjure_zst() });
+        }
+        match unsafe { (*head).pop() } {
             Some(val) => Some(val),
-            None => {
-                let curr_tail = self.tail.load(Acquire);
-                if head == curr_tail {
-                    return None; // Queue is empty
-                }
-                // Move to next segment
-                let curr_segment = unsafe { Box::from_raw(head) };
-                let next_segment_ptr = curr_segment.next_block.load(Acquire);
-                self.head.store(next_segment_ptr, Release);
-                let next_segment = unsafe { &*next_segment_ptr };
-                unsafe { next_segment.pop() }
+            None => self.pop_handoff(head),
+        }
+    }
+
+    /// Slow path of [`pop`](Self::pop), taken after `head` was seen empty.
+    ///
+    /// The producer links a successor only once a segment is full and never
+    /// writes to it afterwards, so a non-null `next_block` seals the segment.
+    /// Values may still have been pushed between the failed pop and the seal;
+    /// the segment is released only after those are drained too.
+    #[cold]
+    fn pop_handoff(&self, head: *mut Segment<T>) -> Option<T> {
+        let segment = unsafe { &*head };
+        // Acquire pairs with the Release in `link_new_block`, making every
+        // value pushed into the sealed segment visible.
+        let next = segment.next_block.load(Acquire);
+        if next.is_null() {
+            return None; // Queue is empty
+        }
+        if let Some(val) = unsafe { segment.pop() } {
+            return Some(val);
+        }
+
+        unsafe {
+            self.release_head(head, next);
+            (*next).pop()
+        }
+    }
+
+    /// Returns a reference to the oldest value without removing it.
+    ///
+    /// Like [`pop`](Self::pop), this releases the head segment once it is
+    /// sealed and drained.
+    ///
+    /// # Safety
+    /// The same single-consumer rule as for [`pop`](Self::pop) applies, and
+    /// the consumer must not pop while the returned reference is alive.
+    pub unsafe fn peek(&self) -> Option<&T> {
+        let head = self.head.load(Relaxed);
+        let segment = unsafe { &*head };
+        if Self::IS_ZST {
+            let queued = !segment.is_empty();
+            return queued.then(|| unsafe { NonNull::dangling().as_ref() });
+        }
+        if let Some(val) = unsafe { segment.peek() } {
+            return Some(val);
+        }
+        let next = segment.next_block.load(Acquire);
+        if next.is_null() {
+            return None;
+        }
+        if let Some(val) = unsafe { segment.peek() } {
+            return Some(val);
+        }
+        unsafe {
+            self.release_head(head, next);
+            (*next).peek()
+        }
+    }
+
+    /// Makes `next` the head segment and hands `head` to the spare pool, or
+    /// frees it if the pool is full.
+    ///
+    /// # Safety
+    /// `head` must be the current head segment, sealed and drained, and `next`
+    /// its successor.
+    unsafe fn release_head(&self, head: *mut Segment<T>, next: *mut Segment<T>) {
+        // Sealed and drained: nothing can reach this segment any more.
+        self.head.store(next, Release);
+        let segment = unsafe { &*head };
+        segment.reset();
+        // A slot seen empty stays empty: only the consumer fills slots.
+        match self.spares.iter().find(|slot| slot.load(Relaxed).is_null()) {
+            // Release publishes the reset to the producer taking the spare.
+            Some(slot) => slot.store(head, Release),
+            None => unsafe { self.free_segment(head) },
+        }
+    }
+
+    /// Allocates an empty segment of `slots` slots. Only the producer may
+    /// call this.
+    fn alloc_segment(&self, slots: usize) -> Result<*mut Segment<T>, ChannelError> {
+        let segment = Segment::try_new_in(slots, &self.alloc)?;
+        self.bytes.fetch_add(Segment::<T>::footprint(slots), Relaxed);
+        Ok(segment)
+    }
+
+    /// Frees a segment that neither side can reach any more.
+    ///
+    /// # Safety
+    /// `segment` must have come from the queue's own allocations and be
+    /// unreachable from the queue and the spare pool.
+    unsafe fn free_segment(&self, segment: *mut Segment<T>) {
+        let slots = unsafe { (*segment).slots() };
+        self.bytes.fetch_sub(Segment::<T>::footprint(slots), Relaxed);
+        self.freed.fetch_add(1, Relaxed);
+        unsafe { Segment::free_in(segment, &self.alloc) };
+    }
+
+    /// Picks the length of the segment to link after the full `tail`.
+    ///
+    /// The length doubles, up to the maximum, while the consumer is still on
+    /// an earlier segment; otherwise it stays the same. Only the producer may
+    /// call this.
+    fn next_segment_slots(&self, tail: &Segment<T>) -> usize {
+        // Only compared, never dereferenced: a stale head just delays growth.
+        let lagging = !ptr::eq(self.head.load(Relaxed), tail);
+        if lagging {
+            (tail.slots() * 2).min(self.max_slots)
+        } else {
+            tail.slots()
+        }
+    }
+
+    /// Takes a spare segment of `slots` slots out of the pool, if there is one.
+    ///
+    /// Spares of any other length were drained before the segments grew and
+    /// are freed on the way. Only the producer may call this.
+    fn take_spare(&self, slots: usize) -> Option<*mut Segment<T>> {
+        for slot in self.spares.iter() {
+            // Acquire pairs with the Release in `release_head`; the swap keeps
+            // the consumer from shrinking the pool under our feet.
+            let spare = slot.swap(null_mut(), Acquire);
+            if spare.is_null() {
+                continue;
+            }
+            if unsafe { (*spare).slots() } == slots {
+                return Some(spare);
+            }
+            unsafe { self.free_segment(spare) };
+        }
+        None
+    }
+
+    /// Returns unused memory to the allocator: the head segment if it is
+    /// drained and the producer has moved on, and every spare segment.
+    ///
+    /// # Safety
+    /// Only the consumer may call this; see [`pop`](Self::pop).
+    pub unsafe fn shrink(&self) {
+        if Self::IS_ZST {
+            return; // A single segment, and never a spare
+        }
+        let head = self.head.load(Relaxed);
+        let segment = unsafe { &*head };
+        // Acquire makes every value pushed into the sealed head visible.
+        let next = segment.next_block.load(Acquire);
+        if !next.is_null() && segment.is_empty() {
+            unsafe { self.release_head(head, next) };
+        }
+        for slot in self.spares.iter() {
+            let spare = slot.swap(null_mut(), Acquire);
+            if !spare.is_null() {
+                unsafe { self.free_segment(spare) };
             }
         }
     }
+
+    /// Returns `true` if the head segment is also the tail: everything
+    /// queued fits in a single segment.
+    ///
+    /// # Safety
+    /// Only the consumer may call this; see [`pop`](Self::pop).
+    pub unsafe fn is_single_segment(&self) -> bool {
+        ptr::eq(self.head.load(Relaxed), self.tail.load(Acquire))
+    }
+
+    /// Returns `true` if the next push would add to the segments held: the
+    /// tail segment is full and there is no spare to link in its place.
+    ///
+    /// A spare too short to reuse still counts, since the push frees it before
+    /// allocating.
+    ///
+    /// # Safety
+    /// Only the producer may call this; see [`try_push`](Self::try_push).
+    pub unsafe fn push_adds_segment(&self) -> bool {
+        if Self::IS_ZST {
+            return false;
+        }
+        let tail = unsafe { &*self.tail.load(Acquire) };
+        tail.is_full() && self.spares.iter().all(|slot| slot.load(Relaxed).is_null())
+    }
+
+    /// Returns the number of values in the queue.
+    ///
+    /// Every segment but the first and last is full, so the walk is cheap,
+    /// but it is still linear in the number of segments.
+    ///
+    /// # Safety
+    /// Only the consumer may call this, since it walks the segments from the
+    /// head; see [`pop`](Self::pop).
+    pub unsafe fn len(&self) -> usize {
+        if Self::IS_ZST {
+            let segment = unsafe { &*self.head.load(Relaxed) };
+            let pushed = segment.next_head.load(Acquire);
+            return pushed.wrapping_sub(segment.tail.load(Relaxed));
+        }
+        let mut len = 0;
+        let mut curr = self.head.load(Relaxed);
+        while !curr.is_null() {
+            let segment = unsafe { &*curr };
+            len += segment.len();
+            curr = segment.next_block.load(Acquire);
+        }
+        len
+    }
+
+    /// Returns the number of segments allocated since creation, including the first one.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub fn segments_allocated(&self) -> usize {
+        self.allocated.load(Relaxed)
+    }
+
+    /// Returns the number of times the producer reused a spare segment
+    /// instead of allocating one.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub fn segments_recycled(&self) -> usize {
+        self.recycled.load(Relaxed)
+    }
+
+    /// Returns the number of segments the consumer has freed since creation,
+    /// not counting those kept as spares.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub fn segments_freed(&self) -> usize {
+        self.freed.load(Relaxed)
+    }
+
+    /// Returns the number of segments currently held, linked or spare.
+    pub fn segments_held(&self) -> usize {
+        self.allocated.load(Relaxed).wrapping_sub(self.freed.load(Relaxed))
+    }
+
+    /// Returns the number of bytes held by segments, linked or spare,
+    /// counting each segment's header as well as its buffer.
+    pub fn footprint(&self) -> usize {
+        self.bytes.load(Relaxed)
+    }
+
+    /// Returns `true` if there is nothing to pop.
+    ///
+    /// # Safety
+    /// Only the consumer may call this, since it dereferences the head
+    /// segment; see [`pop`](Self::pop).
+    pub unsafe fn is_empty(&self) -> bool {
+        let head = unsafe { &*self.head.load(Acquire) };
+        // A sealed head only stays empty until the consumer moves past it, so
+        // the queue is empty exactly when its successor is empty too. That
+        // successor cannot be sealed in turn: only full segments are.
+        let next = head.next_block.load(Acquire);
+        head.is_empty() && (next.is_null() || unsafe { (*next).is_empty() })
+    }
 }
 
-impl<T> Drop for RawSpsc<T> {
+// The segments are only reachable through `AtomicPtr`s, which would make the
+// queue `Send` and `Sync` for any `T`. Values cross from the producer's thread
+// to the consumer's, so require `T: Send` explicitly.
+unsafe impl<T: Send, A: SpscAlloc> Send for RawSpsc<T, A> {}
+unsafe impl<T: Send, A: SpscAlloc> Sync for RawSpsc<T, A> {}
+
+impl<T, A: SpscAlloc> Drop for RawSpsc<T, A> {
     /// Drops all linked segments starting from the head up to the tail,
     /// ensuring no memory leaks occur.
+    ///
+    /// A value that panics while its segment is dropped does not stop the
+    /// remaining segments from being dropped and freed.
     fn drop(&mut self) {
-        let head = self.head.load(Acquire);
-        let tail = self.tail.load(Acquire);
-        let mut curr = head;
-
-        while curr != tail {
-            let segment = unsafe { Box::from_raw(curr) };
-            curr = segment.next_block.load(Acquire);
+        if Self::IS_ZST {
+            let queued = unsafe { self.len() };
+            // Mark the only segment drained, then drop the counted values.
+            let segment = self.head.load(Acquire);
+            unsafe {
+                (*segment).tail.store((*segment).next_head.load(Relaxed), Relaxed);
+                Segment::free_in(segment, &self.alloc);
+            }
+            for_each_unwinding(0..queued, |_| drop(unsafe { conjure_zst::<T>() }));
+            return;
         }
-        // Drop the tail segment
-        _ = unsafe { Box::from_raw(curr) };
+        for slot in self.spares.iter() {
+            let spare = slot.load(Acquire);
+            if !spare.is_null() {
+                // Drained when it was parked, so this cannot panic.
+                unsafe { Segment::free_in(spare, &self.alloc) };
+            }
+        }
+        let head = self.head.load(Acquire);
+        // The successor is read before a segment is yielded and freed.
+        let segments = std::iter::successors(Some(head), |&curr| {
+            let next = unsafe { (*curr).next_block.load(Acquire) };
+            (!next.is_null()).then_some(next)
+        });
+        for_each_unwinding(segments, |curr| unsafe { Segment::free_in(curr, &self.alloc) });
     }
 }
 
+/// Produces a value of the zero-sized type `T` out of thin air.
+///
+/// # Safety
+/// `T` must be zero-sized, and each call must stand in for a value of `T`
+/// that was forgotten earlier, so that no more values exist than were created.
+unsafe fn conjure_zst<T>() -> T {
+    debug_assert_eq!(mem::size_of::<T>(), 0);
+    unsafe { NonNull::<T>::dangling().as_ptr().read() }
+}
+
 /// Internal queue segment containing a fixed-size ring buffer of elements.
 ///
 /// Each `Segment` maintains atomic head and tail indices to manage push/pop operations in a lock-free manner.
@@ -121,35 +626,66 @@ impl<T> Drop for RawSpsc<T> {
 /// # Details
 /// - `next_head` is the atomic index where the producer will push the next element.
 /// - `tail` is the atomic index where the consumer will pop the next element.
-/// - `ptr` points to a contiguous buffer of `MaybeUninit<T>` elements of size `SEGMENT_SIZE`.
+/// - `ptr` points to a contiguous buffer of `MaybeUninit<T>` slots, a power of two of them.
+/// - `mask` is that number of slots minus one.
 /// - `next_block` is an atomic pointer to the next `Segment` in the linked list.
 ///
-/// The ring buffer uses wrapping arithmetic modulo `SEGMENT_SIZE`.
+/// The ring buffer uses wrapping arithmetic modulo its number of slots.
 struct Segment<T> {
     next_head: CachePadded<AtomicUsize>,
     tail: CachePadded<AtomicUsize>,
-    ptr: NonNull<MaybeUninit<T>>,
+    ptr: NonNull<Slot<T>>,
+    mask: usize,
     next_block: AtomicPtr<Segment<T>>,
 }
 
 impl<T> Segment<T> {
-    /// Creates a new `Segment` with an allocated buffer of `SEGMENT_SIZE` elements.
-    pub fn new() -> Self {
+    /// Allocates a new `Segment` with a buffer of `slots` elements, which
+    /// must be a power of two. The segment and its buffer both come from
+    /// `alloc`, and go back to it through [`free_in`](Self::free_in).
+    ///
+    /// Fails without leaking anything if either allocation fails.
+    pub fn try_new_in<A: SpscAlloc>(
+        slots: usize,
+        alloc: &A,
+    ) -> Result<*mut Segment<T>, ChannelError> {
+        debug_assert!(slots.is_power_of_two());
         let next_head = CachePadded::new(AtomicUsize::new(0));
         let tail = CachePadded::new(AtomicUsize::new(0));
 
-        let layout = Layout::array::<MaybeUninit<T>>(SEGMENT_SIZE).unwrap();
-        let ptr = NonNull::new(unsafe { alloc(layout) as *mut MaybeUninit<T> })
-            .expect("unable to allocate Memory");
+        let layout =
+            Layout::array::<Slot<T>>(slots).map_err(|_| ChannelError::LayoutOverflow)?;
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling() // Zero-sized slots need no memory
+        } else {
+            let ptr = alloc.allocate(layout)?.cast::<Slot<T>>();
+            for idx in 0..slots {
+                unsafe { ptr.as_ptr().add(idx).write(UnsafeCell::new(MaybeUninit::uninit())) };
+            }
+            ptr
+        };
 
-        let next_block = null_mut::<Segment<T>>().into();
+        let next_block = AtomicPtr::new(null_mut::<Segment<T>>());
 
-        Segment {
-            next_head,
-            tail,
-            ptr,
-            next_block,
-        }
+        let segment = match alloc.allocate(Layout::new::<Segment<T>>()) {
+            Ok(segment) => segment.cast::<Segment<T>>(),
+            Err(err) => {
+                if layout.size() != 0 {
+                    unsafe { alloc.deallocate(ptr.cast(), layout) };
+                }
+                return Err(err.into());
+            }
+        };
+        unsafe {
+            segment.write(Segment {
+                next_head,
+                tail,
+                ptr,
+                mask: slots - 1,
+                next_block,
+            })
+        };
+        Ok(segment.as_ptr())
     }
 
     /// Attempts to push a value into this segment.
@@ -160,14 +696,14 @@ impl<T> Segment<T> {
     /// This function is unsafe because it performs raw pointer writes.
     pub unsafe fn push(&self, value: T) -> Result<(), T> {
         let curr_head = self.next_head.load(Relaxed);
-        let next_head = (curr_head + 1) & MASK;
+        let next_head = (curr_head + 1) & self.mask;
 
         if next_head == self.tail.load(Acquire) {
             return Err(value); // Segment is full
         }
         unsafe  {
-            let ptr = self.ptr.as_ptr().add(curr_head);
-            (*ptr).write(value);
+            let slot = &*self.ptr.as_ptr().add(curr_head);
+            slot.with_mut(|ptr| (*ptr).write(value));
         }
         self.next_head.store(next_head, Release);
         Ok(())
@@ -185,61 +721,120 @@ impl<T> Segment<T> {
             return None; // Segment is empty
         }
 
-        let value = unsafe {(*self.ptr.as_ptr().add(curr_tail)).assume_init_read()};
-        let next_tail = (curr_tail + 1) & MASK;
+        let value = unsafe {
+            let slot = &*self.ptr.as_ptr().add(curr_tail);
+            slot.with(|ptr| (*ptr).assume_init_read())
+        };
+        let next_tail = (curr_tail + 1) & self.mask;
 
         self.tail.store(next_tail, Release);
         Some(value)
     }
 
-    /// Allocates a new segment and links it as the next block of this segment.
+    /// Returns a reference to the oldest element without removing it.
     ///
-    /// Returns a raw pointer to the newly created segment.
-    pub fn link_new_block(&self) -> *mut Segment<T> {
-        let new_block_segment = Box::new(Self::new());
-        let next_block = Box::into_raw(new_block_segment);
+    /// # Safety
+    /// Only the consumer may call this, and it must not pop while the
+    /// returned reference is alive.
+    pub unsafe fn peek(&self) -> Option<&T> {
+        let curr_tail = self.tail.load(Relaxed);
+        if self.next_head.load(Acquire) == curr_tail {
+            return None; // Segment is empty
+        }
+        unsafe {
+            let slot = &*self.ptr.as_ptr().add(curr_tail);
+            Some(&*slot.with(|ptr| (*ptr).as_ptr()))
+        }
+    }
+
+    /// Returns the number of elements in this segment.
+    pub fn len(&self) -> usize {
+        let head = self.next_head.load(Acquire);
+        let tail = self.tail.load(Relaxed);
+        head.wrapping_sub(tail) & self.mask
+    }
+
+    /// Returns the length of this segment's buffer, in slots.
+    pub fn slots(&self) -> usize {
+        self.mask + 1
+    }
+
+    /// Returns the bytes a segment of `slots` slots occupies, header included.
+    pub fn footprint(slots: usize) -> usize {
+        mem::size_of::<Segment<T>>() + slots * mem::size_of::<Slot<T>>()
+    }
+
+    /// Returns `true` if the producer cannot push into this segment.
+    pub fn is_full(&self) -> bool {
+        (self.next_head.load(Relaxed) + 1) & self.mask == self.tail.load(Acquire)
+    }
+
+    /// Returns `true` if this segment holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.next_head.load(Acquire) == self.tail.load(Relaxed)
+    }
+
+    /// Empties a drained segment and unlinks it, ready to be linked again.
+    ///
+    /// Only the segment's last owner may call this, once neither side can
+    /// reach it any more.
+    pub fn reset(&self) {
+        self.next_head.store(0, Relaxed);
+        self.tail.store(0, Relaxed);
+        self.next_block.store(null_mut(), Relaxed);
+    }
+
+    /// Links the empty segment `next_block` as the next block of this segment.
+    pub fn link_new_block(&self, next_block: *mut Segment<T>) {
         self.next_block.store(next_block, Release);
-        next_block
     }
 
-    /// Allocates a new segment, links it as the next block, and pushes the value into it.
+    /// Links the empty segment `next_block`, and pushes the value into it.
     ///
-    /// Returns a raw pointer to the newly created segment.
+    /// Returns `next_block` back.
     ///
     /// # Safety
-    /// This function is unsafe because it dereferences raw pointers.
-    pub unsafe fn link_and_push(&self, value: T) -> *mut Segment<T> {
-        let new_block_ptr = self.link_new_block();
+    /// `next_block` must point to an empty, unlinked segment that only the
+    /// producer can reach.
+    pub unsafe fn link_and_push(&self, next_block: *mut Segment<T>, value: T) -> *mut Segment<T> {
+        self.link_new_block(next_block);
         unsafe {
-            let new_block = &*new_block_ptr;
+            let new_block = &*next_block;
             _ = new_block.push(value);
         }
-        new_block_ptr
+        next_block
     }
 }
 
-impl<T> Drop for Segment<T> {
-    /// Drops all initialized elements within the segment and deallocates its buffer.
-    fn drop(&mut self) {
-        let head = self.next_head.load(Acquire);
-        let tail = self.tail.load(Acquire);
-        let mut idx = tail;
-        // Iterate from tail to head, dropping all initialized elements
-        while idx != head {
-            unsafe {
-                let ptr = self.ptr.as_ptr().add(idx);
-                (*ptr).assume_init_drop();
+impl<T> Segment<T> {
+    /// Drops all initialized elements within the segment, then returns the
+    /// segment and its buffer to `alloc`.
+    ///
+    /// The memory is freed even if one of the elements panics.
+    ///
+    /// # Safety
+    /// `segment` must come from [`try_new_in`](Self::try_new_in) with the same
+    /// allocator, be unreachable by either side, and not be used afterwards.
+    pub unsafe fn free_in<A: SpscAlloc>(segment: *mut Segment<T>, alloc: &A) {
+        let this = unsafe { &*segment };
+        let (buffer, slots) = (this.ptr, this.slots());
+        let _dealloc = OnDrop::new(|| unsafe {
+            let layout = Layout::array::<Slot<T>>(slots).unwrap();
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(buffer.as_ptr(), slots));
+            if layout.size() != 0 {
+                alloc.deallocate(buffer.cast(), layout);
             }
-            idx += 1;
-            idx &= MASK;
-        }
+            ptr::drop_in_place(segment);
+            alloc.deallocate(NonNull::new_unchecked(segment).cast(), Layout::new::<Segment<T>>());
+        });
 
-        let layout = Layout::array::<MaybeUninit<T>>(SEGMENT_SIZE).unwrap();
-        let ptr = self.ptr.as_ptr() as *mut u8;
-
-        unsafe {
-            dealloc(ptr, layout);
-        }
+        let tail = this.tail.load(Acquire);
+        // Iterate from tail to head, dropping all initialized elements
+        let occupied = (0..this.len()).map(|offset| (tail + offset) & this.mask);
+        for_each_unwinding(occupied, |idx| unsafe {
+            let slot = &*buffer.as_ptr().add(idx);
+            slot.with_mut(|ptr| (*ptr).assume_init_drop());
+        });
     }
 }
 
@@ -249,16 +844,19 @@ mod tests {
     use std::sync::Arc;
     use std::thread;
 
-    use super::RawSpsc; // adjust path if needed
+    use super::{RawSpsc, SEGMENT_SIZE, Segment, SegmentSize};
+    use std::sync::atomic::Ordering::Relaxed;
 
     const COUNT: usize = 100_000;
 
+    // Every test keeps to one producer thread and one consumer thread.
+
     #[test]
     fn basic_push_pop_test() {
         let queue = RawSpsc::new();
         for i in 0..1000 {
-            queue.push(i);
-            let popped = queue.pop();
+            unsafe { queue.push(i) };
+            let popped = unsafe { queue.pop() };
             assert_eq!(popped, Some(i));
         }
     }
@@ -268,15 +866,160 @@ mod tests {
         let queue = RawSpsc::new();
 
         for i in 0..COUNT {
-            queue.push(i);
+            unsafe { queue.push(i) };
         }
 
         for i in 0..COUNT {
-            let val = queue.pop();
+            let val = unsafe { queue.pop() };
             assert_eq!(val, Some(i));
         }
 
-        assert_eq!(queue.pop(), None); // Should now be empty
+        assert_eq!(unsafe { queue.pop() }, None); // Should now be empty
+    }
+
+    #[test]
+    fn segment_counters() {
+        let queue = RawSpsc::with_spare_segments(0);
+        assert_eq!(queue.segments_allocated(), 1);
+        for i in 0..1000 {
+            unsafe { queue.push(i) };
+        }
+        let allocated = queue.segments_allocated();
+        assert!(allocated > 1);
+        while unsafe { queue.pop() }.is_some() {}
+        assert_eq!(queue.segments_freed(), allocated - 1); // The tail segment stays
+    }
+
+    #[test]
+    fn drained_segments_are_recycled() {
+        // Every round crosses one segment boundary and drains the queue again.
+        let queue = RawSpsc::new();
+        for round in 0..10 {
+            for i in 0..SEGMENT_SIZE {
+                unsafe { queue.push(round * SEGMENT_SIZE + i) };
+            }
+            for i in 0..SEGMENT_SIZE {
+                assert_eq!(unsafe { queue.pop() }, Some(round * SEGMENT_SIZE + i));
+            }
+        }
+        assert_eq!(queue.segments_allocated(), 2);
+        assert_eq!(queue.segments_recycled(), 9);
+        assert_eq!(queue.segments_freed(), 0);
+
+        // With a larger pool, a burst leaves several spares behind.
+        let queue = RawSpsc::with_spare_segments(4);
+        for i in 0..10 * SEGMENT_SIZE {
+            unsafe { queue.push(i) };
+        }
+        while unsafe { queue.pop() }.is_some() {}
+        let allocated = queue.segments_allocated();
+        assert_eq!(queue.segments_freed(), allocated - 1 - 4);
+        for i in 0..4 * SEGMENT_SIZE {
+            unsafe { queue.push(i) };
+        }
+        assert_eq!(queue.segments_allocated(), allocated);
+        assert_eq!(queue.segments_recycled(), 4);
+    }
+
+    #[test]
+    fn shrink_releases_drained_head_and_spares() {
+        let queue = RawSpsc::with_spare_segments(4);
+        let one = Segment::<usize>::footprint(SEGMENT_SIZE);
+        assert_eq!(queue.footprint(), one);
+        // Three full segments and one value in a fourth.
+        for i in 0..3 * (SEGMENT_SIZE - 1) + 1 {
+            unsafe { queue.push(i) };
+        }
+        assert_eq!(queue.footprint(), 4 * one);
+        // Drain up to the end of the third segment: two spares, a drained head.
+        for i in 0..3 * (SEGMENT_SIZE - 1) {
+            assert_eq!(unsafe { queue.pop() }, Some(i));
+        }
+        assert_eq!(queue.segments_held(), 4);
+        unsafe { queue.shrink() };
+        assert_eq!((queue.segments_held(), queue.footprint()), (1, one));
+        assert_eq!(queue.segments_freed(), 3);
+        assert_eq!(unsafe { queue.pop() }, Some(3 * (SEGMENT_SIZE - 1)));
+    }
+
+    /// Returns the length of every linked segment, from the head on.
+    fn segment_slots<T>(queue: &RawSpsc<T>) -> Vec<usize> {
+        let head = queue.head.load(Relaxed);
+        std::iter::successors(Some(head), |&curr| {
+            let next = unsafe { (*curr).next_block.load(Relaxed) };
+            (!next.is_null()).then_some(next)
+        })
+        .map(|curr| unsafe { (*curr).slots() })
+        .collect()
+    }
+
+    #[test]
+    fn segment_size_rounding() {
+        assert_eq!(SegmentSize::default().initial_slots(), SEGMENT_SIZE);
+        assert_eq!(SegmentSize::slots(100).initial_slots(), 128);
+        assert_eq!(SegmentSize::slots(0).initial_slots(), 2);
+        assert_eq!(SegmentSize::bytes::<u8>(4096).initial_slots(), 4096);
+        assert_eq!(SegmentSize::bytes::<[u8; 4096]>(96 * 1024).initial_slots(), 16);
+        assert_eq!(SegmentSize::bytes::<[u8; 4096]>(100).initial_slots(), 2);
+        assert_eq!(SegmentSize::bytes::<()>(1).initial_slots(), SEGMENT_SIZE);
+        let size = SegmentSize::slots(16).growing_to(8);
+        assert_eq!((size.initial_slots(), size.max_slots()), (16, 16));
+    }
+
+    #[test]
+    fn segments_grow_while_the_consumer_lags() {
+        let queue = RawSpsc::with_options(SegmentSize::slots(4).growing_to(32), 1);
+        for i in 0..100 {
+            unsafe { queue.push(i) };
+        }
+        // The second segment is linked while the consumer is still on the
+        // first one, but not yet a whole segment behind.
+        assert_eq!(segment_slots(&queue), [4, 4, 8, 16, 32, 32, 32]);
+        assert!((0..100).all(|i| unsafe { queue.pop() } == Some(i)));
+        assert_eq!(segment_slots(&queue), [32]);
+        // The drained 4-slot segment was kept as a spare; the next segment
+        // is 32 slots long, so it is freed instead of reused.
+        let freed = queue.segments_freed();
+        for i in 0..32 {
+            unsafe { queue.push(i) };
+        }
+        assert_eq!(segment_slots(&queue), [32, 32]);
+        assert_eq!(queue.segments_freed(), freed + 1);
+        assert_eq!(queue.segments_recycled(), 0);
+    }
+
+    #[test]
+    fn segments_keep_their_length_while_the_consumer_keeps_up() {
+        let queue = RawSpsc::with_options(SegmentSize::slots(4).growing_to(32), 1);
+        // Every round fills a segment and links the next, then drains both.
+        for round in 0..10 {
+            for i in 0..4 {
+                unsafe { queue.push(round * 4 + i) };
+            }
+            for i in 0..4 {
+                assert_eq!(unsafe { queue.pop() }, Some(round * 4 + i));
+            }
+        }
+        assert_eq!(segment_slots(&queue), [4]);
+        assert_eq!(queue.segments_allocated(), 2);
+    }
+
+    #[test]
+    fn handoff_keeps_values_pushed_after_empty_check() {
+        let queue = RawSpsc::with_spare_segments(0);
+        // Consumer: the head segment looks empty...
+        let head = queue.head.load(Relaxed);
+        assert_eq!(unsafe { (*head).pop() }, None);
+        // ...producer: fills it, which links a second segment...
+        for i in 0..SEGMENT_SIZE {
+            unsafe { queue.push(i) };
+        }
+        assert_ne!(queue.tail.load(Relaxed), head);
+        // ...consumer: resumes where it left off, and must not drop the old segment.
+        assert_eq!(queue.pop_handoff(head), Some(0));
+        let rest: Vec<_> = std::iter::from_fn(|| unsafe { queue.pop() }).collect();
+        assert_eq!(rest, (1..SEGMENT_SIZE).collect::<Vec<_>>());
+        assert_eq!(queue.segments_freed(), 1);
     }
 
     #[test]
@@ -286,7 +1029,7 @@ mod tests {
             let queue = queue.clone();
             thread::spawn(move || {
                 for i in 0..COUNT {
-                    queue.push(i);
+                    unsafe { queue.push(i) };
                 }
             })
         };
@@ -296,7 +1039,7 @@ mod tests {
             thread::spawn(move || {
                 for i in 0..COUNT {
                     loop {
-                        if let Some(val) = queue.pop() {
+                        if let Some(val) = unsafe { queue.pop() } {
                             assert_eq!(val, i);
                             break;
                         }
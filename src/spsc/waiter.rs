@@ -0,0 +1,269 @@
+//! Thread parking used by the blocking channel operations.
+//!
+//! The lock-free queues never block on their own. When a caller asks to wait,
+//! its thread registers a [`Waiter`] with one or more [`WaitSlot`]s and parks.
+//! The other side of the channel calls [`WaitSlot::notify`] after every
+//! successful operation, which costs a relaxed load while nobody is waiting.
+//!
+//! # Lost wake-ups
+//!
+//! The waiting side stores `armed = true`, issues a fence, and then re-checks
+//! the queue. The notifying side updates the queue, issues a fence, and then
+//! loads `armed`. The two fences guarantee that at least one of the sides
+//! observes the other's store, so a waiter can never sleep through an item
+//! that was published before it parked.
+//!
+//! Notifying happens on every send and receive, waiting rarely, so the fences
+//! are asymmetric where the platform allows it: on Linux the waiting side
+//! issues an expedited `membarrier`, which runs a full barrier on every
+//! thread of the process, and the notifying side needs only a compiler fence.
+//! Elsewhere, or if the kernel refuses `membarrier`, both sides issue a
+//! `SeqCst` fence.
+
+use crate::spsc::builder::WaitStrategy;
+use std::hint;
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering::{Acquire, Relaxed, Release, SeqCst},
+    compiler_fence, fence,
+};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+/// A thread that is (or is about to be) parked on one or more [`WaitSlot`]s.
+pub(crate) struct Waiter {
+    thread: Thread,
+    notified: AtomicBool,
+}
+
+thread_local! {
+    static CURRENT: Arc<Waiter> = Arc::new(Waiter {
+        thread: thread::current(),
+        notified: AtomicBool::new(false),
+    });
+}
+
+impl Waiter {
+    /// Returns the waiter belonging to the calling thread.
+    pub(crate) fn current() -> Arc<Self> {
+        CURRENT.with(Arc::clone)
+    }
+
+    /// Wakes the parked thread.
+    pub(crate) fn wake(&self) {
+        self.notified.store(true, Release);
+        self.thread.unpark();
+    }
+
+    /// Parks the calling thread until [`wake`](Self::wake) is called or `deadline` passes.
+    ///
+    /// Returns `false` if the deadline passed without a wake-up.
+    fn park(&self, deadline: Option<Instant>) -> bool {
+        loop {
+            if self.notified.load(Acquire) {
+                return true;
+            }
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+    }
+}
+
+/// A registration point for at most one [`Waiter`].
+///
+/// Every channel keeps one slot per side that can block. Only the side that
+/// owns the slot registers with it; the opposite side only notifies.
+///
+/// The type is `pub` only so the sealed select trait can name it; the module
+/// itself is private to the crate.
+pub struct WaitSlot {
+    armed: AtomicBool,
+    /// Whether the waiting side issues the heavy fence, fixed before the slot
+    /// is shared so that both sides always agree on it.
+    asymmetric: bool,
+    waiter: Mutex<Option<Arc<Waiter>>>,
+}
+
+impl WaitSlot {
+    pub(crate) fn new() -> Self {
+        Self {
+            armed: AtomicBool::new(false),
+            asymmetric: membarrier::available(),
+            waiter: Mutex::new(None),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Arc<Waiter>>> {
+        self.waiter.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Registers `waiter` to be woken by the next [`notify`](Self::notify).
+    pub(crate) fn register(&self, waiter: &Arc<Waiter>) {
+        *self.lock() = Some(Arc::clone(waiter));
+        self.armed.store(true, Relaxed);
+        // Pairs with the fence in `notify`: see the module documentation.
+        fence(SeqCst);
+        if self.asymmetric {
+            membarrier::heavy_fence();
+        }
+    }
+
+    /// Removes any registered waiter.
+    pub(crate) fn unregister(&self) {
+        self.armed.store(false, Relaxed);
+        self.lock().take();
+    }
+
+    /// Wakes the registered waiter, if any.
+    ///
+    /// Must be called after the state change the waiter is interested in has
+    /// been published.
+    #[inline(always)]
+    pub(crate) fn notify(&self) {
+        if self.asymmetric {
+            compiler_fence(SeqCst);
+        } else {
+            fence(SeqCst);
+        }
+        if self.armed.load(Relaxed) {
+            self.notify_slow();
+        }
+    }
+
+    #[cold]
+    fn notify_slow(&self) {
+        if let Some(waiter) = self.lock().as_ref() {
+            waiter.wake();
+        }
+    }
+}
+
+/// The heavy half of the asymmetric fence in [`WaitSlot`].
+#[cfg(all(target_os = "linux", not(miri)))]
+mod membarrier {
+    use std::sync::OnceLock;
+
+    const MEMBARRIER_CMD_PRIVATE_EXPEDITED: libc::c_long = 1 << 3;
+    const MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED: libc::c_long = 1 << 4;
+
+    /// Returns `true` if the process may issue expedited barriers,
+    /// registering it on the first call.
+    pub(super) fn available() -> bool {
+        static REGISTERED: OnceLock<bool> = OnceLock::new();
+        *REGISTERED.get_or_init(|| {
+            let cmd = MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED;
+            unsafe { libc::syscall(libc::SYS_membarrier, cmd, 0, 0) == 0 }
+        })
+    }
+
+    /// Runs a full memory barrier on every running thread of the process.
+    pub(super) fn heavy_fence() {
+        let cmd = MEMBARRIER_CMD_PRIVATE_EXPEDITED;
+        let ret = unsafe { libc::syscall(libc::SYS_membarrier, cmd, 0, 0) };
+        // Registration succeeded, so the barrier cannot fail; if it ever did,
+        // a notifier could miss the waiter and leave it parked for good.
+        assert_eq!(ret, 0, "membarrier failed after registration");
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(miri))))]
+mod membarrier {
+    pub(super) fn available() -> bool {
+        false
+    }
+
+    pub(super) fn heavy_fence() {}
+}
+
+/// Repeatedly calls `poll` until it returns `Some`, parking on `slots` in between.
+///
+/// `poll` must return `Some` for every terminal state the caller cares about
+/// (a value, a disconnection, ...), otherwise the caller may park forever.
+/// `parked` is told how long each park lasted. Returns `None` only if
+/// `deadline` passed first.
+pub(crate) fn wait_until<R>(
+    slots: &[&WaitSlot],
+    deadline: Option<Instant>,
+    mut parked: impl FnMut(Duration),
+    mut poll: impl FnMut() -> Option<R>,
+) -> Option<R> {
+    if let Some(ready) = poll() {
+        return Some(ready);
+    }
+    let waiter = Waiter::current();
+    loop {
+        waiter.notified.store(false, Relaxed);
+        for slot in slots {
+            slot.register(&waiter);
+        }
+        let ready = poll();
+        let woken = ready.is_some() || {
+            let start = Instant::now();
+            let woken = waiter.park(deadline);
+            parked(start.elapsed());
+            woken
+        };
+        for slot in slots {
+            slot.unregister();
+        }
+        match ready {
+            Some(ready) => return Some(ready),
+            None if !woken => return poll(),
+            None => {}
+        }
+    }
+}
+
+/// Like [`wait_until`], without a deadline.
+pub(crate) fn wait<R>(
+    slots: &[&WaitSlot],
+    parked: impl FnMut(Duration),
+    poll: impl FnMut() -> Option<R>,
+) -> R {
+    match wait_until(slots, None, parked, poll) {
+        Some(ready) => ready,
+        None => unreachable!("waiting without a deadline cannot time out"),
+    }
+}
+
+/// Like [`wait`], but first polls without parking for as long as `strategy`
+/// says; [`WaitStrategy::Spin`] and [`WaitStrategy::Yield`] never park.
+pub(crate) fn wait_with<R>(
+    strategy: WaitStrategy,
+    slots: &[&WaitSlot],
+    parked: impl FnMut(Duration),
+    mut poll: impl FnMut() -> Option<R>,
+) -> R {
+    let spins = match strategy {
+        WaitStrategy::Park => 0,
+        WaitStrategy::SpinThenPark(spins) => spins,
+        WaitStrategy::Spin => loop {
+            if let Some(ready) = poll() {
+                return ready;
+            }
+            hint::spin_loop();
+        },
+        WaitStrategy::Yield => loop {
+            if let Some(ready) = poll() {
+                return ready;
+            }
+            thread::yield_now();
+        },
+    };
+    for _ in 0..spins {
+        if let Some(ready) = poll() {
+            return ready;
+        }
+        hint::spin_loop();
+    }
+    wait(slots, parked, poll)
+}
@@ -0,0 +1,43 @@
+//! Synchronization primitives used by the lock-free queues.
+//!
+//! The queues import their atomics and cells from here instead of `std` so
+//! that building with `--cfg loom` swaps in [`loom`](https://docs.rs/loom)'s
+//! instrumented versions, letting the model checker explore every
+//! interleaving of the `Acquire`/`Release` pairs and catch unsynchronized
+//! slot accesses.
+
+#[cfg(loom)]
+pub(crate) use loom::cell::UnsafeCell;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{AtomicPtr, AtomicUsize};
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{AtomicPtr, AtomicUsize};
+
+/// [`std::cell::UnsafeCell`] with the closure-based access API of loom's cell.
+///
+/// Every access to a slot goes through [`with`](Self::with) or
+/// [`with_mut`](Self::with_mut), which is what allows loom to check that
+/// the producer and the consumer never touch the same slot concurrently.
+#[cfg(not(loom))]
+#[derive(Debug)]
+#[repr(transparent)]
+pub(crate) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    #[inline(always)]
+    pub(crate) const fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    #[inline(always)]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    #[inline(always)]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}